@@ -0,0 +1,322 @@
+use crate::circuit::{ElementType, Netlist, Stamp};
+
+use super::{complex::Complex, mna::Mna, unknown_names, AnalysisError, SimulationResult, Trace};
+
+/// Upper bound on frequency points so a typo in the settings can not exhaust memory
+const MAX_FREQUENCY_POINTS: f64 = 100_000.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SweepType {
+    Linear,
+    Decade,
+    Octave,
+}
+
+impl SweepType {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SweepType::Linear => "Linear",
+            SweepType::Decade => "Decade",
+            SweepType::Octave => "Octave",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AcSettings {
+    pub sweep_type: SweepType,
+    /// Total points of a linear sweep, points per decade or octave otherwise
+    pub points: usize,
+    pub start_frequency: f64,
+    pub stop_frequency: f64,
+    /// Designator of the voltage or current source driven with the AC stimulus, empty picks the
+    /// first voltage source
+    pub source: String,
+    pub magnitude: f64,
+    /// Phase of the stimulus in degrees
+    pub phase: f64,
+}
+
+impl Default for AcSettings {
+    fn default() -> Self {
+        Self {
+            sweep_type: SweepType::Decade,
+            points: 10,
+            start_frequency: 1.0,
+            stop_frequency: 1e6,
+            source: String::new(),
+            magnitude: 1.0,
+            phase: 0.0,
+        }
+    }
+}
+
+/// Complex node voltages and branch currents at every frequency
+pub struct AcResult {
+    pub frequencies: Vec<f64>,
+    /// Name and unit of every unknown, see [`unknown_names`]
+    pub names: Vec<(String, &'static str)>,
+    /// One vector per unknown with a value per frequency
+    pub values: Vec<Vec<Complex>>,
+}
+
+impl AcResult {
+    /// Magnitude in decibels and unwrapped phase in degrees of every unknown, plotted over a
+    /// logarithmic frequency axis unless the sweep was linear
+    pub fn bode(&self, log_sweep: bool) -> SimulationResult {
+        let magnitudes = self
+            .names
+            .iter()
+            .zip(self.values.iter())
+            .map(|((name, _), values)| Trace {
+                name: format!("dB({})", name),
+                unit: "dB",
+                values: values.iter().map(|x| 20.0 * x.abs().log10()).collect(),
+            });
+        let phases = self
+            .names
+            .iter()
+            .zip(self.values.iter())
+            .map(|((name, _), values)| Trace {
+                name: format!("Phase({})", name),
+                unit: "°",
+                values: unwrapped_phase(values),
+            });
+
+        SimulationResult {
+            title: "AC Sweep".to_string(),
+            sweep_name: "Frequency".to_string(),
+            sweep_unit: "Hz",
+            log_sweep,
+            sweep: self.frequencies.clone(),
+            traces: magnitudes.chain(phases).collect(),
+        }
+    }
+}
+
+/// Phase in degrees without the jumps of 360 degrees where it wraps around
+fn unwrapped_phase(values: &[Complex]) -> Vec<f64> {
+    let mut phases = Vec::with_capacity(values.len());
+    for value in values {
+        let mut phase = value.arg().to_degrees();
+        if let Some(previous) = phases.last() {
+            phase += ((previous - phase) / 360.0).round() * 360.0;
+        }
+        phases.push(phase);
+    }
+
+    phases
+}
+
+/// Frequencies of the sweep, both ends included
+pub fn frequencies(settings: &AcSettings) -> Result<Vec<f64>, AnalysisError> {
+    let (start, stop) = (settings.start_frequency, settings.stop_frequency);
+    if start <= 0.0 || stop < start || settings.points == 0 {
+        return Err(AnalysisError::InvalidSettings(
+            "frequencies must be positive, stop at or above start and points above zero"
+                .to_string(),
+        ));
+    }
+
+    let ratio = match settings.sweep_type {
+        SweepType::Linear => None,
+        SweepType::Decade => Some(10.0),
+        SweepType::Octave => Some(2.0),
+    };
+    let count = match ratio {
+        None => settings.points as f64,
+        // A little tolerance so the stop frequency is not lost to rounding
+        Some(ratio) => (settings.points as f64 * (stop / start).log(ratio) + 1e-9).floor() + 1.0,
+    };
+    if count > MAX_FREQUENCY_POINTS {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "more than {} frequency points",
+            MAX_FREQUENCY_POINTS
+        )));
+    }
+    let count = count as usize;
+
+    Ok((0..count)
+        .map(|index| match ratio {
+            None if count == 1 => start,
+            None => start + (stop - start) * index as f64 / (count - 1) as f64,
+            Some(ratio) => start * ratio.powf(index as f64 / settings.points as f64),
+        })
+        .collect())
+}
+
+/// Small signal analysis of the linear circuit. DC sources are zeroed, voltage sources become
+/// shorts and current sources opens, except for the chosen source which carries the stimulus.
+pub fn ac(netlist: &Netlist, settings: &AcSettings) -> Result<AcResult, AnalysisError> {
+    let frequencies = frequencies(settings)?;
+    let source = stimulus_source(netlist, &settings.source)?;
+    let stimulus = Complex::from_polar(settings.magnitude, settings.phase.to_radians());
+
+    let names = unknown_names(netlist);
+    let mut values = vec![Vec::with_capacity(frequencies.len()); names.len()];
+    for frequency in frequencies.iter() {
+        let solution = solve_frequency(netlist, source, stimulus, *frequency)?;
+        for (values, value) in values.iter_mut().zip(solution) {
+            values.push(value);
+        }
+    }
+
+    Ok(AcResult {
+        frequencies,
+        names,
+        values,
+    })
+}
+
+/// Index of the netlist element named `name`, or of the first voltage source without a name
+fn stimulus_source(netlist: &Netlist, name: &str) -> Result<usize, AnalysisError> {
+    let is_source = |element: &ElementType| {
+        matches!(
+            element,
+            ElementType::DCVoltageSource(_) | ElementType::DCCurrentSource(_)
+        )
+    };
+
+    let index = match name {
+        "" => netlist
+            .elements
+            .iter()
+            .position(|x| matches!(x.element, ElementType::DCVoltageSource(_))),
+        name => netlist
+            .elements
+            .iter()
+            .position(|x| x.name.eq_ignore_ascii_case(name) && is_source(&x.element)),
+    };
+
+    index.ok_or_else(|| match name {
+        "" => AnalysisError::InvalidSettings("the circuit has no voltage source".to_string()),
+        name => {
+            AnalysisError::InvalidSettings(format!("{} is not a voltage or current source", name))
+        }
+    })
+}
+
+fn solve_frequency(
+    netlist: &Netlist,
+    source: usize,
+    stimulus: Complex,
+    frequency: f64,
+) -> Result<Vec<Complex>, AnalysisError> {
+    let omega = 2.0 * std::f64::consts::PI * frequency;
+    laplace_system(netlist, source, stimulus, Complex::new(0.0, omega)).solve()
+}
+
+/// System of the linear circuit at the complex frequency `s`, capacitors are admittances of `sC`
+/// and inductors branches with an impedance of `sL`. Only the element at `source` is driven.
+pub fn laplace_system(
+    netlist: &Netlist,
+    source: usize,
+    stimulus: Complex,
+    s: Complex,
+) -> Mna<Complex> {
+    let zero = Complex::from(0.0);
+    let mut mna = Mna::new(netlist.node_count, netlist.branch_count());
+
+    let stamps = netlist.stamps();
+    for (index, (netlist_element, stamp)) in netlist.elements.iter().zip(stamps).enumerate() {
+        let node1 = netlist_element.node1.0;
+        let node2 = netlist_element.node2.0;
+        let excitation = if index == source { stimulus } else { zero };
+
+        match stamp {
+            Some(Stamp::Resistor(resistance)) => {
+                mna.stamp_conductance(node1, node2, Complex::from(1.0 / resistance));
+            }
+            Some(Stamp::VoltageSource(_, branch)) => {
+                mna.stamp_branch(node1, node2, branch, zero, excitation);
+            }
+            Some(Stamp::CurrentSource(_)) => {
+                mna.stamp_current(node1, node2, excitation);
+            }
+            Some(Stamp::Capacitor(capacitance)) => {
+                mna.stamp_conductance(node1, node2, s * Complex::from(capacitance));
+            }
+            Some(Stamp::Inductor(inductance, branch)) => {
+                let impedance = s * Complex::from(inductance);
+                mna.stamp_branch(node1, node2, branch, impedance, zero);
+            }
+            None => (),
+        }
+    }
+
+    mna
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::rc_low_pass;
+
+    use super::{ac, frequencies, AcSettings, SweepType};
+
+    #[test]
+    fn sweep_frequencies() {
+        let mut settings = AcSettings {
+            sweep_type: SweepType::Decade,
+            points: 2,
+            start_frequency: 1.0,
+            stop_frequency: 100.0,
+            ..Default::default()
+        };
+        let decade = frequencies(&settings).unwrap();
+        assert_eq!(decade.len(), 5);
+        assert!((decade[1] - 10f64.sqrt()).abs() < 1e-9);
+        assert!((decade[4] - 100.0).abs() < 1e-9);
+
+        settings.sweep_type = SweepType::Octave;
+        settings.points = 1;
+        settings.stop_frequency = 8.0;
+        assert_eq!(frequencies(&settings).unwrap().len(), 4);
+
+        settings.sweep_type = SweepType::Linear;
+        settings.points = 3;
+        assert_eq!(frequencies(&settings).unwrap(), [1.0, 4.5, 8.0]);
+
+        settings.start_frequency = 0.0;
+        assert!(frequencies(&settings).is_err());
+    }
+
+    #[test]
+    fn low_pass_corner() {
+        let corner = 1.0 / (2.0 * std::f64::consts::PI * 1e-3);
+        let settings = AcSettings {
+            sweep_type: SweepType::Linear,
+            points: 1,
+            start_frequency: corner,
+            stop_frequency: corner,
+            ..Default::default()
+        };
+
+        let result = ac(&rc_low_pass(), &settings).unwrap();
+        let output = result.values[1][0];
+        assert_eq!(result.names[1].0, "V2");
+        assert!((output.abs() - 0.5f64.sqrt()).abs() < 1e-6);
+        assert!((output.arg().to_degrees() + 45.0).abs() < 1e-4);
+
+        let bode = result.bode(false);
+        let magnitude = bode.traces.iter().find(|x| x.name == "dB(V2)").unwrap();
+        assert!((magnitude.values[0] + 3.0103).abs() < 1e-3);
+        let phase = bode.traces.iter().find(|x| x.name == "Phase(V2)").unwrap();
+        assert!((phase.values[0] + 45.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn source_lookup() {
+        let settings = AcSettings {
+            source: "R1".to_string(),
+            ..Default::default()
+        };
+        assert!(ac(&rc_low_pass(), &settings).is_err());
+
+        // Designators match in any case like SPICE
+        let settings = AcSettings {
+            source: "v1".to_string(),
+            ..Default::default()
+        };
+        assert!(ac(&rc_low_pass(), &settings).is_ok());
+    }
+}
@@ -0,0 +1,291 @@
+use crate::{
+    circuit::{ElementType, Netlist},
+    utils::si::format_si,
+};
+
+use super::{unknown_names, AnalysisError, SimulationResult, Trace};
+
+/// Upper bound on sweep points so a typo in the settings can not exhaust memory
+const MAX_SWEEP_POINTS: f64 = 100_000.0;
+
+/// Values a DC source is stepped through
+#[derive(Clone)]
+pub struct SweepSource {
+    /// Designator of the voltage or current source
+    pub source: String,
+    pub start: f64,
+    pub stop: f64,
+    pub step: f64,
+}
+
+impl Default for SweepSource {
+    fn default() -> Self {
+        Self {
+            source: String::new(),
+            start: 0.0,
+            stop: 5.0,
+            step: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct DcSweepSettings {
+    pub primary: SweepSource,
+    /// Stepped once per full sweep of the primary source when `nested` is set
+    pub secondary: SweepSource,
+    pub nested: bool,
+}
+
+/// Values from start to stop in steps of `step`, counting down when stop is below start. The
+/// stop value is always included.
+pub fn sweep_values(sweep_source: &SweepSource) -> Result<Vec<f64>, AnalysisError> {
+    let SweepSource {
+        start, stop, step, ..
+    } = *sweep_source;
+    if step <= 0.0 || !start.is_finite() || !stop.is_finite() {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "the step of {} must be positive",
+            sweep_source.source
+        )));
+    }
+
+    // A little tolerance so the stop value is not lost to rounding
+    let intervals = ((stop - start).abs() / step + 1e-9).floor();
+    if intervals + 1.0 > MAX_SWEEP_POINTS {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "more than {} sweep points",
+            MAX_SWEEP_POINTS
+        )));
+    }
+
+    let direction = if stop < start { -1.0 } else { 1.0 };
+    let mut values = (0..=intervals as usize)
+        .map(|index| start + direction * step * index as f64)
+        .collect::<Vec<f64>>();
+    if values
+        .last()
+        .is_some_and(|x| (x - stop).abs() > step * 1e-6)
+    {
+        values.push(stop);
+    }
+
+    Ok(values)
+}
+
+/// Steps the primary source through its values, and the secondary source around it when nested,
+/// solving the operating point with `operating_point` at every point. Every node voltage and
+/// branch current becomes a trace over the primary source value, nested sweeps get one trace
+/// per secondary value with that value in its name.
+pub fn dc_sweep(
+    netlist: &Netlist,
+    settings: &DcSweepSettings,
+    mut operating_point: impl FnMut(&Netlist) -> Result<Vec<f64>, AnalysisError>,
+) -> Result<SimulationResult, AnalysisError> {
+    let primary = source_index(netlist, &settings.primary.source)?;
+    let sweep = sweep_values(&settings.primary)?;
+    let secondary = match settings.nested {
+        true => Some((
+            source_index(netlist, &settings.secondary.source)?,
+            sweep_values(&settings.secondary)?,
+        )),
+        false => None,
+    };
+    let secondary_count = secondary.as_ref().map_or(1, |(_, values)| values.len());
+    if (sweep.len() * secondary_count) as f64 > MAX_SWEEP_POINTS {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "more than {} sweep points",
+            MAX_SWEEP_POINTS
+        )));
+    }
+
+    let names = unknown_names(netlist);
+    let mut traces = vec![];
+    let mut swept = netlist.clone();
+    for secondary_step in 0..secondary_count {
+        let mut suffix = String::new();
+        if let Some((index, values)) = &secondary {
+            let element = &mut swept.elements[*index];
+            set_value(&mut element.element, values[secondary_step]);
+            suffix = format!(
+                " ({}={})",
+                element.name,
+                format_si(values[secondary_step], source_unit(&element.element))
+            );
+        }
+
+        let mut values = vec![Vec::with_capacity(sweep.len()); names.len()];
+        for value in sweep.iter() {
+            set_value(&mut swept.elements[primary].element, *value);
+            let solution = operating_point(&swept)?;
+            for (values, value) in values.iter_mut().zip(solution) {
+                values.push(value);
+            }
+        }
+
+        traces.extend(
+            names
+                .iter()
+                .zip(values)
+                .map(|((name, unit), values)| Trace {
+                    name: format!("{}{}", name, suffix),
+                    unit,
+                    values,
+                }),
+        );
+    }
+
+    let primary_element = &netlist.elements[primary];
+    Ok(SimulationResult {
+        title: "DC Sweep".to_string(),
+        sweep_name: primary_element.name.clone(),
+        sweep_unit: source_unit(&primary_element.element),
+        log_sweep: false,
+        sweep,
+        traces,
+    })
+}
+
+fn source_index(netlist: &Netlist, name: &str) -> Result<usize, AnalysisError> {
+    netlist
+        .elements
+        .iter()
+        .position(|x| {
+            x.name.eq_ignore_ascii_case(name)
+                && matches!(
+                    x.element,
+                    ElementType::DCVoltageSource(_) | ElementType::DCCurrentSource(_)
+                )
+        })
+        .ok_or_else(|| {
+            AnalysisError::InvalidSettings(format!(
+                "{:?} is not a DC voltage or current source",
+                name
+            ))
+        })
+}
+
+fn set_value(element: &mut ElementType, value: f64) {
+    if let Some(element_value) = element.value_mut() {
+        *element_value = value as f32;
+    }
+}
+
+fn source_unit(element: &ElementType) -> &'static str {
+    match element {
+        ElementType::DCCurrentSource(_) => "A",
+        _ => "V",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{element, AnalysisError, SimulationResult},
+        circuit::{ElementType, Netlist},
+    };
+
+    use super::{dc_sweep, sweep_values, DcSweepSettings, SweepSource};
+
+    /// V1 and V2 in series on node 1 and 2, 1k to node 3 and 1k to ground
+    fn series_sources() -> Netlist {
+        Netlist {
+            node_count: 4,
+            node_labels: vec![None; 4],
+            elements: vec![
+                element("V1", ElementType::DCVoltageSource(1.0), 1, 0),
+                element("V2", ElementType::DCVoltageSource(0.0), 2, 1),
+                element("R1", ElementType::Resistor(1000.0), 2, 3),
+                element("R2", ElementType::Resistor(1000.0), 3, 0),
+            ],
+        }
+    }
+
+    /// Operating point of [`series_sources`] worked out by hand
+    fn solve_series_sources(netlist: &Netlist) -> Result<Vec<f64>, AnalysisError> {
+        let value = |index: usize| netlist.elements[index].element.value().unwrap() as f64;
+        let (v1, v2) = (value(0), value(1));
+        let current = (v1 + v2) / 2000.0;
+
+        Ok(vec![v1, v1 + v2, (v1 + v2) / 2.0, current, current])
+    }
+
+    fn trace<'a>(result: &'a SimulationResult, name: &str) -> &'a [f64] {
+        &result
+            .traces
+            .iter()
+            .find(|x| x.name == name)
+            .unwrap()
+            .values
+    }
+
+    fn sweep_source(source: &str, start: f64, stop: f64, step: f64) -> SweepSource {
+        SweepSource {
+            source: source.to_string(),
+            start,
+            stop,
+            step,
+        }
+    }
+
+    #[test]
+    fn values_include_stop() {
+        assert_eq!(
+            sweep_values(&sweep_source("V1", 0.0, 1.0, 0.5)).unwrap(),
+            [0.0, 0.5, 1.0]
+        );
+        assert_eq!(
+            sweep_values(&sweep_source("V1", 1.0, 0.0, 0.25)).unwrap(),
+            [1.0, 0.75, 0.5, 0.25, 0.0]
+        );
+        assert_eq!(
+            sweep_values(&sweep_source("V1", 0.0, 1.0, 0.4)).unwrap(),
+            [0.0, 0.4, 0.8, 1.0]
+        );
+        assert_eq!(
+            sweep_values(&sweep_source("V1", 2.0, 2.0, 1.0)).unwrap(),
+            [2.0]
+        );
+        assert!(sweep_values(&sweep_source("V1", 0.0, 1.0, 0.0)).is_err());
+    }
+
+    #[test]
+    fn nested_sweep() {
+        let settings = DcSweepSettings {
+            primary: sweep_source("V1", 0.0, 2.0, 1.0),
+            secondary: sweep_source("V2", 0.0, 1.0, 1.0),
+            nested: true,
+        };
+
+        let result = dc_sweep(&series_sources(), &settings, solve_series_sources).unwrap();
+        assert_eq!(result.sweep, [0.0, 1.0, 2.0]);
+        assert_eq!(result.sweep_name, "V1");
+        assert_eq!(result.traces.len(), 10);
+        assert_eq!(trace(&result, "V3 (V2=0 V)"), [0.0, 0.5, 1.0]);
+        assert_eq!(trace(&result, "V3 (V2=1 V)"), [0.5, 1.0, 1.5]);
+
+        let settings = DcSweepSettings {
+            nested: false,
+            ..settings
+        };
+        let result = dc_sweep(&series_sources(), &settings, solve_series_sources).unwrap();
+        assert_eq!(trace(&result, "V3"), [0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn only_sources_can_be_swept() {
+        let settings = DcSweepSettings {
+            primary: sweep_source("R1", 0.0, 2.0, 1.0),
+            ..Default::default()
+        };
+
+        assert!(dc_sweep(&series_sources(), &settings, solve_series_sources).is_err());
+
+        // Designators match in any case like SPICE
+        let settings = DcSweepSettings {
+            primary: sweep_source("v1", 0.0, 2.0, 1.0),
+            ..Default::default()
+        };
+        assert!(dc_sweep(&series_sources(), &settings, solve_series_sources).is_ok());
+    }
+}
@@ -0,0 +1,291 @@
+//! Modified nodal analysis used by the transient, AC and pole-zero analyses. spice-rs only
+//! exposes a real valued DC operating point, so it can not step capacitor and inductor companion
+//! models in time, solve complex frequency domain systems or report the determinant. The
+//! analyses stamp the element models of [`Netlist::stamps`](crate::circuit::Netlist::stamps),
+//! which the spice-rs circuit is built from as well, and the DC solution is cross-checked against
+//! [`dc_op`](spice_rs::runners::dc_op::dc_op) in the transient tests.
+
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use super::{complex::Complex, AnalysisError};
+
+/// Conductance tied from every node to ground so floating nodes stay solvable
+pub const GMIN: f64 = 1e-12;
+
+/// Number the system is solved in, real for DC and transient and complex for AC
+pub trait Scalar:
+    Copy
+    + PartialEq
+    + From<f64>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+{
+    /// Size used to pick pivots and detect a singular matrix
+    fn magnitude(self) -> f64;
+}
+
+impl Scalar for f64 {
+    fn magnitude(self) -> f64 {
+        self.abs()
+    }
+}
+
+impl Scalar for Complex {
+    fn magnitude(self) -> f64 {
+        self.abs()
+    }
+}
+
+/// Modified nodal analysis system. Ground is not part of the system, node `n` is row `n - 1`
+/// and branch currents follow the node voltages.
+pub struct Mna<T: Scalar = f64> {
+    size: usize,
+    node_rows: usize,
+    matrix: Vec<T>,
+    rhs: Vec<T>,
+}
+
+impl<T: Scalar> Mna<T> {
+    pub fn new(node_count: usize, branch_count: usize) -> Self {
+        let node_rows = node_count.saturating_sub(1);
+        let size = node_rows + branch_count;
+
+        let mut mna = Self {
+            size,
+            node_rows,
+            matrix: vec![T::from(0.0); size * size],
+            rhs: vec![T::from(0.0); size],
+        };
+        for node in 1..node_count {
+            mna.stamp_conductance(node, 0, T::from(GMIN));
+        }
+
+        mna
+    }
+
+    fn node_row(&self, node: usize) -> Option<usize> {
+        node.checked_sub(1)
+    }
+
+    pub fn branch_row(&self, branch: usize) -> usize {
+        self.node_rows + branch
+    }
+
+    fn add(&mut self, row: usize, column: usize, value: T) {
+        self.matrix[row * self.size + column] += value;
+    }
+
+    pub fn stamp_conductance(&mut self, node1: usize, node2: usize, conductance: T) {
+        let row1 = self.node_row(node1);
+        let row2 = self.node_row(node2);
+
+        if let Some(row1) = row1 {
+            self.add(row1, row1, conductance);
+        }
+        if let Some(row2) = row2 {
+            self.add(row2, row2, conductance);
+        }
+        if let (Some(row1), Some(row2)) = (row1, row2) {
+            self.add(row1, row2, -conductance);
+            self.add(row2, row1, -conductance);
+        }
+    }
+
+    /// Current flowing from `node1` through the source into `node2`
+    pub fn stamp_current(&mut self, node1: usize, node2: usize, current: T) {
+        if let Some(row1) = self.node_row(node1) {
+            self.rhs[row1] -= current;
+        }
+        if let Some(row2) = self.node_row(node2) {
+            self.rhs[row2] += current;
+        }
+    }
+
+    /// Branch whose current flows from `node1` through the element into `node2`, with the
+    /// branch equation `V(node1) - V(node2) - resistance * I = voltage`
+    pub fn stamp_branch(
+        &mut self,
+        node1: usize,
+        node2: usize,
+        branch: usize,
+        resistance: T,
+        voltage: T,
+    ) {
+        let branch_row = self.branch_row(branch);
+        let one = T::from(1.0);
+
+        if let Some(row1) = self.node_row(node1) {
+            self.add(row1, branch_row, one);
+            self.add(branch_row, row1, one);
+        }
+        if let Some(row2) = self.node_row(node2) {
+            self.add(row2, branch_row, -one);
+            self.add(branch_row, row2, -one);
+        }
+        self.add(branch_row, branch_row, -resistance);
+        self.rhs[branch_row] += voltage;
+    }
+
+    /// Branch that forces its own current, used for inductors with a known current
+    pub fn stamp_branch_current(&mut self, node1: usize, node2: usize, branch: usize, current: T) {
+        let branch_row = self.branch_row(branch);
+        let one = T::from(1.0);
+
+        if let Some(row1) = self.node_row(node1) {
+            self.add(row1, branch_row, one);
+        }
+        if let Some(row2) = self.node_row(node2) {
+            self.add(row2, branch_row, -one);
+        }
+        self.add(branch_row, branch_row, one);
+        self.rhs[branch_row] += current;
+    }
+
+    /// Solves the system with gaussian elimination and partial pivoting
+    pub fn solve(self) -> Result<Vec<T>, AnalysisError> {
+        self.solve_with_determinant().map(|(solution, _)| solution)
+    }
+
+    /// Solves the system and also returns the determinant of the matrix
+    pub fn solve_with_determinant(mut self) -> Result<(Vec<T>, T), AnalysisError> {
+        let size = self.size;
+        let zero = T::from(0.0);
+        let mut determinant = T::from(1.0);
+
+        // A zero resistance or inductance stamps an infinite conductance, which elimination
+        // would spread as NaN through the whole solution
+        if self
+            .matrix
+            .iter()
+            .chain(self.rhs.iter())
+            .any(|x| !x.magnitude().is_finite())
+        {
+            return Err(AnalysisError::InvalidSettings(
+                "an element value gives an infinite conductance".to_string(),
+            ));
+        }
+
+        for column in 0..size {
+            let pivot_row = (column..size)
+                .max_by(|a, b| {
+                    self.matrix[a * size + column]
+                        .magnitude()
+                        .total_cmp(&self.matrix[b * size + column].magnitude())
+                })
+                .unwrap();
+            if self.matrix[pivot_row * size + column].magnitude() < 1e-18 {
+                return Err(AnalysisError::Singular);
+            }
+
+            if pivot_row != column {
+                for k in 0..size {
+                    self.matrix.swap(column * size + k, pivot_row * size + k);
+                }
+                self.rhs.swap(column, pivot_row);
+                determinant = -determinant;
+            }
+
+            let pivot = self.matrix[column * size + column];
+            determinant = determinant * pivot;
+            for row in column + 1..size {
+                let factor = self.matrix[row * size + column] / pivot;
+                if factor == zero {
+                    continue;
+                }
+
+                for k in column..size {
+                    let value = factor * self.matrix[column * size + k];
+                    self.matrix[row * size + k] -= value;
+                }
+                let value = factor * self.rhs[column];
+                self.rhs[row] -= value;
+            }
+        }
+
+        let mut solution = vec![zero; size];
+        for row in (0..size).rev() {
+            let sum = (row + 1..size)
+                .map(|k| self.matrix[row * size + k] * solution[k])
+                .fold(zero, |a, b| a + b);
+            solution[row] = (self.rhs[row] - sum) / self.matrix[row * size + row];
+        }
+        if solution.iter().any(|x| !x.magnitude().is_finite()) {
+            return Err(AnalysisError::Singular);
+        }
+
+        Ok((solution, determinant))
+    }
+}
+
+/// Voltage of `node` in a solution, ground is always 0
+pub fn node_voltage<T: Scalar>(solution: &[T], node: usize) -> T {
+    match node {
+        0 => T::from(0.0),
+        node => solution[node - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{complex::Complex, AnalysisError};
+
+    use super::Mna;
+
+    #[test]
+    fn voltage_divider() {
+        // 10V source on node 1, 1k from node 1 to 2 and 1k from node 2 to ground
+        let mut mna = Mna::new(3, 1);
+        mna.stamp_branch(1, 0, 0, 0.0, 10.0);
+        mna.stamp_conductance(1, 2, 1e-3);
+        mna.stamp_conductance(2, 0, 1e-3);
+
+        let solution = mna.solve().unwrap();
+        assert!((solution[0] - 10.0).abs() < 1e-6);
+        assert!((solution[1] - 5.0).abs() < 1e-6);
+        // Current leaves the positive terminal into the resistors
+        assert!((solution[2] + 5e-3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn current_source() {
+        // 1mA pushed into node 1 through 1k to ground
+        let mut mna = Mna::new(2, 0);
+        mna.stamp_current(0, 1, 1e-3);
+        mna.stamp_conductance(1, 0, 1e-3);
+
+        let (solution, determinant) = mna.solve_with_determinant().unwrap();
+        assert!((solution[0] - 1.0).abs() < 1e-6);
+        assert!((determinant - 1e-3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn complex_divider() {
+        // 1V on node 1, 1 ohm to node 2 and an admittance of j to ground gives 1 / (1 + j)
+        let mut mna = Mna::new(3, 1);
+        mna.stamp_branch(1, 0, 0, Complex::from(0.0), Complex::from(1.0));
+        mna.stamp_conductance(1, 2, Complex::from(1.0));
+        mna.stamp_conductance(2, 0, Complex::new(0.0, 1.0));
+
+        let (solution, determinant) = mna.solve_with_determinant().unwrap();
+        assert!((solution[1] - Complex::new(0.5, -0.5)).abs() < 1e-9);
+        // Expanding along the branch row leaves -(1 + j)
+        assert!((determinant + Complex::new(1.0, 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn infinite_conductance() {
+        let mut mna = Mna::new(2, 0);
+        mna.stamp_current(0, 1, 1e-3);
+        mna.stamp_conductance(1, 0, 1.0 / 0.0);
+
+        assert!(matches!(
+            mna.solve(),
+            Err(AnalysisError::InvalidSettings(_))
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+#[cfg(test)]
+use spice_rs::NodeId;
+
+use crate::circuit::Netlist;
+#[cfg(test)]
+use crate::circuit::{ElementType, NetlistElement};
+
+pub mod ac;
+pub mod complex;
+pub mod dc_sweep;
+pub mod mna;
+pub mod monte_carlo;
+pub mod parametric;
+pub mod pole_zero;
+pub mod sensitivity;
+pub mod transfer_function;
+pub mod transient;
+
+#[derive(Debug)]
+pub enum AnalysisError {
+    /// The circuit has no ground connected to a wire
+    NoGround,
+    /// The system has no unique solution, usually a floating node or a loop of voltage sources
+    Singular,
+    InvalidSettings(String),
+    /// Error reported by the spice-rs solver
+    Solver(String),
+}
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::NoGround => write!(f, "Circuit has no connected ground"),
+            AnalysisError::Singular => write!(
+                f,
+                "Circuit matrix is singular, check for floating nodes or voltage source loops"
+            ),
+            AnalysisError::InvalidSettings(reason) => write!(f, "Invalid settings: {}", reason),
+            AnalysisError::Solver(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// A named result vector, sampled at every point of the sweep
+#[derive(Clone)]
+pub struct Trace {
+    pub name: String,
+    pub unit: &'static str,
+    pub values: Vec<f64>,
+}
+
+/// Result of an analysis, every trace has one value per sweep point
+#[derive(Clone)]
+pub struct SimulationResult {
+    pub title: String,
+    /// Name of the independent variable such as time
+    pub sweep_name: String,
+    pub sweep_unit: &'static str,
+    /// Plot the sweep on a logarithmic axis, used for frequencies
+    pub log_sweep: bool,
+    pub sweep: Vec<f64>,
+    pub traces: Vec<Trace>,
+}
+
+impl SimulationResult {
+    /// Comma separated table with the sweep in the first column and a column per trace
+    pub fn to_csv(&self) -> String {
+        let header = |name: &str, unit: &str| format!("\"{} ({})\"", name.replace('"', "'"), unit);
+        let mut csv = std::iter::once(header(&self.sweep_name, self.sweep_unit))
+            .chain(self.traces.iter().map(|x| header(&x.name, x.unit)))
+            .collect::<Vec<String>>()
+            .join(",");
+        csv.push('\n');
+
+        for (index, sweep) in self.sweep.iter().enumerate() {
+            let row = std::iter::once(*sweep)
+                .chain(self.traces.iter().map(|x| x.values[index]))
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(",");
+            csv.push_str(&row);
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// Names of the solution vector, node voltages first and branch currents after. Labeled nodes
+/// are named after their label such as `V(out)`, branch currents after the voltage source or
+/// inductor they flow through such as `I(V1)`.
+pub fn unknown_names(netlist: &Netlist) -> Vec<(String, &'static str)> {
+    let node_names = (1..netlist.node_count).map(|node| {
+        let name = match &netlist.node_labels[node] {
+            Some(label) => format!("V({})", label),
+            None => format!("V{}", node),
+        };
+        (name, "V")
+    });
+    let branch_names = netlist
+        .elements
+        .iter()
+        .zip(netlist.stamps())
+        .filter(|(_, stamp)| stamp.is_some_and(|x| x.branch().is_some()))
+        .map(|(x, _)| (format!("I({})", x.name), "A"));
+
+    node_names.chain(branch_names).collect()
+}
+
+/// Netlist element for the analysis tests
+#[cfg(test)]
+pub(crate) fn element(
+    name: &str,
+    element: ElementType,
+    node1: usize,
+    node2: usize,
+) -> NetlistElement {
+    NetlistElement {
+        id: 0,
+        name: name.to_string(),
+        element,
+        node1: NodeId(node1),
+        node2: NodeId(node2),
+    }
+}
+
+/// Netlist of unlabeled nodes with the element ids numbered in order from 0
+#[cfg(test)]
+pub(crate) fn numbered_netlist(node_count: usize, elements: Vec<NetlistElement>) -> Netlist {
+    Netlist {
+        node_count,
+        node_labels: vec![None; node_count],
+        elements: elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, x)| NetlistElement {
+                id: index as u32,
+                ..x
+            })
+            .collect(),
+    }
+}
+
+/// 10V from V1 over R1 1k into `out` and R2 3k to ground, element ids count up from 1
+#[cfg(test)]
+pub(crate) fn divider() -> Netlist {
+    let elements = [
+        element("V1", ElementType::DCVoltageSource(10.0), 1, 0),
+        element("R1", ElementType::Resistor(1000.0), 1, 2),
+        element("R2", ElementType::Resistor(3000.0), 2, 0),
+    ];
+
+    Netlist {
+        node_count: 3,
+        node_labels: vec![None, None, Some("out".to_string())],
+        elements: elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, x)| NetlistElement {
+                id: index as u32 + 1,
+                ..x
+            })
+            .collect(),
+    }
+}
+
+/// Operating point of [`divider`] worked out by hand, elements added after the first three are
+/// ignored
+#[cfg(test)]
+pub(crate) fn solve_divider(netlist: &Netlist) -> Result<Vec<f64>, AnalysisError> {
+    let value = |index: usize| netlist.elements[index].element.value().unwrap() as f64;
+    let (v1, r1, r2) = (value(0), value(1), value(2));
+
+    Ok(vec![v1, v1 * r2 / (r1 + r2), -v1 / (r1 + r2)])
+}
+
+/// 1V from V1 over R1 1k into node 2 and C1 1u to ground, a time constant of 1ms and a corner
+/// at 159.15Hz
+#[cfg(test)]
+pub(crate) fn rc_low_pass() -> Netlist {
+    Netlist {
+        node_count: 3,
+        node_labels: vec![None; 3],
+        elements: vec![
+            element("V1", ElementType::DCVoltageSource(1.0), 1, 0),
+            element("R1", ElementType::Resistor(1000.0), 1, 2),
+            element("C1", ElementType::Capacitor(1e-6), 2, 0),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SimulationResult, Trace};
+
+    #[test]
+    fn csv_columns() {
+        let result = SimulationResult {
+            title: "DC Sweep".to_string(),
+            sweep_name: "V1".to_string(),
+            sweep_unit: "V",
+            log_sweep: false,
+            sweep: vec![0.0, 0.5],
+            traces: vec![Trace {
+                name: "I(V1)".to_string(),
+                unit: "A",
+                values: vec![0.0, -0.001],
+            }],
+        };
+
+        assert_eq!(
+            result.to_csv(),
+            "\"V1 (V)\",\"I(V1) (A)\"\n0,0\n0.5,-0.001\n"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use hashbrown::HashMap;
+
+use crate::{
+    circuit::{Distribution, Netlist, Tolerance},
+    utils::random::Random,
+};
+
+use super::{unknown_names, AnalysisError};
+
+/// Upper bound on runs, every run is a full analysis
+const MAX_RUNS: usize = 100_000;
+
+/// Analysis run on every randomized circuit, the value of every unknown at its end is recorded
+#[derive(Clone, Copy, PartialEq)]
+pub enum MonteCarloAnalysis {
+    OperatingPoint,
+    /// Values at the stop time
+    Transient,
+    /// Magnitudes at the stop frequency
+    Ac,
+}
+
+impl MonteCarloAnalysis {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            MonteCarloAnalysis::OperatingPoint => "DC Operating Point",
+            MonteCarloAnalysis::Transient => "Transient (at stop time)",
+            MonteCarloAnalysis::Ac => "AC Sweep (magnitude at stop frequency)",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MonteCarloSettings {
+    pub runs: usize,
+    /// Seed of the random values, the same seed repeats the same circuits
+    pub seed: u64,
+    pub analysis: MonteCarloAnalysis,
+}
+
+impl Default for MonteCarloSettings {
+    fn default() -> Self {
+        Self {
+            runs: 100,
+            seed: 1,
+            analysis: MonteCarloAnalysis::OperatingPoint,
+        }
+    }
+}
+
+/// Minimum, maximum, mean and sample standard deviation of a set of values
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Statistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+impl Statistics {
+    pub fn new(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let squares = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+
+        Some(Self {
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean,
+            std_dev: match values.len() {
+                1 => 0.0,
+                _ => (squares / (count - 1.0)).sqrt(),
+            },
+        })
+    }
+}
+
+/// Recorded value of every unknown in every run
+pub struct MonteCarloResult {
+    pub title: String,
+    /// Name and unit of every unknown, see [`unknown_names`]
+    pub names: Vec<(String, &'static str)>,
+    /// One vector per unknown with a value per run
+    pub samples: Vec<Vec<f64>>,
+}
+
+impl MonteCarloResult {
+    pub fn statistics(&self, index: usize) -> Option<Statistics> {
+        Statistics::new(self.samples.get(index)?)
+    }
+}
+
+/// Counts of `values` in `bins` equally wide bins between the minimum and maximum value, the
+/// maximum is counted in the last bin
+pub fn histogram(values: &[f64], bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    let Some(statistics) = Statistics::new(values) else {
+        return counts;
+    };
+    if bins == 0 {
+        return counts;
+    }
+
+    let width = statistics.max - statistics.min;
+    for value in values {
+        let bin = match width > 0.0 {
+            true => ((value - statistics.min) / width * bins as f64) as usize,
+            false => bins / 2,
+        };
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    counts
+}
+
+/// Copy of the netlist with every element that has a tolerance in `tolerances`, by element id,
+/// set to a random value within it
+pub fn randomize(
+    netlist: &Netlist,
+    tolerances: &HashMap<u32, Tolerance>,
+    random: &mut Random,
+) -> Netlist {
+    let mut randomized = netlist.clone();
+    for netlist_element in randomized.elements.iter_mut() {
+        let Some(tolerance) = tolerances.get(&netlist_element.id) else {
+            continue;
+        };
+        let Some(value) = netlist_element.element.value_mut() else {
+            continue;
+        };
+        if tolerance.percent <= 0.0 {
+            continue;
+        }
+
+        let deviation = match tolerance.distribution {
+            Distribution::Uniform => 2.0 * random.uniform() - 1.0,
+            Distribution::Gaussian => (random.gaussian() / 3.0).clamp(-1.0, 1.0),
+        };
+        *value *= 1.0 + (deviation * tolerance.percent as f64 / 100.0) as f32;
+    }
+
+    randomized
+}
+
+/// Runs `analysis` on `settings.runs` randomized copies of the netlist. `analysis` returns the
+/// value of every unknown in [`unknown_names`] order.
+pub fn monte_carlo(
+    netlist: &Netlist,
+    tolerances: &HashMap<u32, Tolerance>,
+    settings: &MonteCarloSettings,
+    mut analysis: impl FnMut(&Netlist) -> Result<Vec<f64>, AnalysisError>,
+) -> Result<MonteCarloResult, AnalysisError> {
+    if settings.runs == 0 || settings.runs > MAX_RUNS {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "between 1 and {} runs are needed",
+            MAX_RUNS
+        )));
+    }
+
+    let names = unknown_names(netlist);
+    let mut samples = vec![Vec::with_capacity(settings.runs); names.len()];
+    let mut random = Random::new(settings.seed);
+    for _ in 0..settings.runs {
+        let values = analysis(&randomize(netlist, tolerances, &mut random))?;
+        for (samples, value) in samples.iter_mut().zip(values) {
+            samples.push(value);
+        }
+    }
+
+    Ok(MonteCarloResult {
+        title: format!(
+            "Monte Carlo, {} runs of {}",
+            settings.runs,
+            settings.analysis.display_name()
+        ),
+        names,
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+
+    use crate::{
+        analysis::{divider, solve_divider},
+        circuit::{Distribution, Tolerance},
+    };
+
+    use super::{histogram, monte_carlo, MonteCarloSettings, Statistics};
+
+    #[test]
+    fn statistics_and_histogram() {
+        let values = [1.0, 2.0, 3.0, 4.0];
+        let statistics = Statistics::new(&values).unwrap();
+        assert_eq!(statistics.min, 1.0);
+        assert_eq!(statistics.max, 4.0);
+        assert_eq!(statistics.mean, 2.5);
+        assert!((statistics.std_dev - (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!(Statistics::new(&[]).is_none());
+
+        assert_eq!(histogram(&values, 2), [2, 2]);
+        assert_eq!(histogram(&values, 3), [1, 1, 2]);
+        assert_eq!(histogram(&[5.0, 5.0], 3), [0, 2, 0]);
+    }
+
+    #[test]
+    fn runs_are_reproducible_and_within_tolerance() {
+        let mut tolerances = HashMap::new();
+        for (id, distribution) in [(2, Distribution::Uniform), (3, Distribution::Gaussian)] {
+            tolerances.insert(
+                id,
+                Tolerance {
+                    percent: 10.0,
+                    distribution,
+                },
+            );
+        }
+        let settings = MonteCarloSettings {
+            runs: 200,
+            seed: 42,
+            ..Default::default()
+        };
+
+        let result = monte_carlo(&divider(), &tolerances, &settings, solve_divider).unwrap();
+        let again = monte_carlo(&divider(), &tolerances, &settings, solve_divider).unwrap();
+        assert_eq!(result.samples, again.samples);
+        assert_eq!(result.names[1].0, "V(out)");
+
+        // The source has no tolerance, both resistors are at most 10% off
+        let source = result.statistics(0).unwrap();
+        assert_eq!((source.min, source.max), (10.0, 10.0));
+        let output = result.statistics(1).unwrap();
+        let output_at = |r1: f64, r2: f64| 10.0 * r2 / (r1 + r2);
+        assert!(output.min >= output_at(1100.0, 2700.0) - 1e-9);
+        assert!(output.max <= output_at(900.0, 3300.0) + 1e-9);
+        assert!(output.min < output.max);
+        assert!((output.mean - 7.5).abs() < 0.05);
+
+        let settings = MonteCarloSettings {
+            runs: 0,
+            ..settings
+        };
+        assert!(monte_carlo(&divider(), &tolerances, &settings, solve_divider).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    circuit::{ElementType, Netlist},
+    spice_import::parse_spice_value,
+    utils::si::format_si,
+};
+
+use super::{AnalysisError, SimulationResult, Trace};
+
+/// Upper bound on parameter values, every value is a full analysis run
+const MAX_PARAMETER_VALUES: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParameterSpacing {
+    /// Values typed in as a list such as `1k, 2.2k, 4.7k`
+    List,
+    Linear,
+    Logarithmic,
+}
+
+impl ParameterSpacing {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ParameterSpacing::List => "List",
+            ParameterSpacing::Linear => "Linear",
+            ParameterSpacing::Logarithmic => "Logarithmic",
+        }
+    }
+}
+
+/// Analysis repeated for every parameter value, each uses the settings of its own runner window
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParametricAnalysis {
+    Transient,
+    Ac,
+    DcSweep,
+}
+
+impl ParametricAnalysis {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ParametricAnalysis::Transient => "Transient",
+            ParametricAnalysis::Ac => "AC Sweep",
+            ParametricAnalysis::DcSweep => "DC Sweep",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ParametricSettings {
+    /// Designator of the element whose value is stepped
+    pub element: String,
+    pub spacing: ParameterSpacing,
+    /// Comma or space separated values in SPICE notation for [`ParameterSpacing::List`]
+    pub list: String,
+    pub start: f64,
+    pub stop: f64,
+    pub points: usize,
+    pub analysis: ParametricAnalysis,
+}
+
+impl Default for ParametricSettings {
+    fn default() -> Self {
+        Self {
+            element: String::new(),
+            spacing: ParameterSpacing::List,
+            list: "1k, 2.2k, 4.7k".to_string(),
+            start: 1e3,
+            stop: 1e4,
+            points: 3,
+            analysis: ParametricAnalysis::Transient,
+        }
+    }
+}
+
+/// Values the element is set to, in the order they are run
+pub fn parameter_values(settings: &ParametricSettings) -> Result<Vec<f64>, AnalysisError> {
+    let (start, stop, points) = (settings.start, settings.stop, settings.points);
+    let values = match settings.spacing {
+        ParameterSpacing::List => settings
+            .list
+            .split(|x: char| x == ',' || x.is_whitespace())
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                parse_spice_value(x).map(|x| x as f64).ok_or_else(|| {
+                    AnalysisError::InvalidSettings(format!("{:?} is not a value", x))
+                })
+            })
+            .collect::<Result<Vec<f64>, AnalysisError>>()?,
+        _ if points == 0 => vec![],
+        _ if points == 1 => vec![start],
+        ParameterSpacing::Linear => (0..points)
+            .map(|index| start + (stop - start) * index as f64 / (points - 1) as f64)
+            .collect(),
+        ParameterSpacing::Logarithmic if start <= 0.0 || stop <= 0.0 => {
+            return Err(AnalysisError::InvalidSettings(
+                "a logarithmic range needs positive start and stop values".to_string(),
+            ));
+        }
+        ParameterSpacing::Logarithmic => (0..points)
+            .map(|index| start * (stop / start).powf(index as f64 / (points - 1) as f64))
+            .collect(),
+    };
+
+    if values.is_empty() || values.len() > MAX_PARAMETER_VALUES {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "between 1 and {} parameter values are needed",
+            MAX_PARAMETER_VALUES
+        )));
+    }
+
+    Ok(values)
+}
+
+/// Runs `analysis` once for every parameter value and overlays the results. Every trace is
+/// named after the value it was run with such as `V2 [R1=2.2 kΩ]`, the runs have to share
+/// their sweep.
+pub fn parametric_sweep(
+    netlist: &Netlist,
+    settings: &ParametricSettings,
+    mut analysis: impl FnMut(&Netlist) -> Result<SimulationResult, AnalysisError>,
+) -> Result<SimulationResult, AnalysisError> {
+    let index = netlist
+        .elements
+        .iter()
+        .position(|x| x.name.eq_ignore_ascii_case(&settings.element) && x.element.value().is_some())
+        .ok_or_else(|| {
+            AnalysisError::InvalidSettings(format!("{:?} has no value to step", settings.element))
+        })?;
+    let values = parameter_values(settings)?;
+    let element = &netlist.elements[index].element;
+    let unit = element.display_unit_symbol().unwrap_or_default();
+
+    // Checked up front so no run is wasted, a resistor, capacitor or inductor of zero or less
+    // is rejected like the ERC does
+    let positive = matches!(
+        element,
+        ElementType::Resistor(_) | ElementType::Capacitor(_) | ElementType::Inductor(_)
+    );
+    if let Some(value) = values
+        .iter()
+        .find(|x| !(**x as f32).is_finite() || (positive && **x <= 0.0))
+    {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "{} can not be {}",
+            settings.element,
+            format_si(*value, unit)
+        )));
+    }
+
+    let mut swept = netlist.clone();
+    let mut combined: Option<SimulationResult> = None;
+    for value in values {
+        if let Some(element_value) = swept.elements[index].element.value_mut() {
+            *element_value = value as f32;
+        }
+        let result = analysis(&swept)?;
+
+        let key = format!("{}={}", settings.element, format_si(value, unit));
+        let traces = result.traces.iter().map(|x| Trace {
+            name: format!("{} [{}]", x.name, key),
+            unit: x.unit,
+            values: x.values.clone(),
+        });
+        match &mut combined {
+            None => {
+                let traces = traces.collect();
+                combined = Some(SimulationResult {
+                    title: format!("{} stepping {}", result.title, settings.element),
+                    traces,
+                    ..result
+                });
+            }
+            Some(combined) if combined.sweep == result.sweep => combined.traces.extend(traces),
+            Some(_) => {
+                return Err(AnalysisError::InvalidSettings(format!(
+                    "the {} changes with {}",
+                    result.sweep_name.to_lowercase(),
+                    key
+                )));
+            }
+        }
+    }
+
+    combined.ok_or_else(|| AnalysisError::InvalidSettings("no parameter values".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{
+        ac::{ac, AcSettings, SweepType},
+        rc_low_pass,
+    };
+
+    use super::{parameter_values, parametric_sweep, ParameterSpacing, ParametricSettings};
+
+    #[test]
+    fn values() {
+        let mut settings = ParametricSettings {
+            list: "1k, 2.2k 4.7k".to_string(),
+            ..Default::default()
+        };
+        let values = parameter_values(&settings).unwrap();
+        assert_eq!(values.len(), 3);
+        assert!((values[1] - 2200.0).abs() < 1e-3);
+
+        settings.spacing = ParameterSpacing::Logarithmic;
+        settings.start = 1.0;
+        settings.stop = 100.0;
+        let values = parameter_values(&settings).unwrap();
+        assert!((values[1] - 10.0).abs() < 1e-9);
+
+        settings.spacing = ParameterSpacing::Linear;
+        assert_eq!(parameter_values(&settings).unwrap(), [1.0, 50.5, 100.0]);
+
+        settings.spacing = ParameterSpacing::List;
+        settings.list = "1k, abc".to_string();
+        assert!(parameter_values(&settings).is_err());
+    }
+
+    #[test]
+    fn resistor_family() {
+        // RC low pass at the corner frequency of the 1k resistor
+        let netlist = rc_low_pass();
+        let corner = 1.0 / (2.0 * std::f64::consts::PI * 1e-3);
+        let ac_settings = AcSettings {
+            sweep_type: SweepType::Linear,
+            points: 1,
+            start_frequency: corner,
+            stop_frequency: corner,
+            ..Default::default()
+        };
+        let settings = ParametricSettings {
+            element: "R1".to_string(),
+            list: "1k 2k".to_string(),
+            ..Default::default()
+        };
+
+        let result = parametric_sweep(&netlist, &settings, |netlist| {
+            ac(netlist, &ac_settings).map(|x| x.bode(false))
+        })
+        .unwrap();
+        let magnitude = |name: &str| {
+            result
+                .traces
+                .iter()
+                .find(|x| x.name == name)
+                .unwrap()
+                .values[0]
+        };
+        assert_eq!(result.traces.len(), 2 * 2 * 3);
+        assert!((magnitude("dB(V2) [R1=1 kΩ]") + 3.0103).abs() < 1e-3);
+        assert!(magnitude("dB(V2) [R1=2 kΩ]") < -6.0);
+
+        let settings = ParametricSettings {
+            element: "V9".to_string(),
+            ..settings
+        };
+        assert!(parametric_sweep(&netlist, &settings, |_| unreachable!()).is_err());
+
+        // Values the element can not take are rejected before any run
+        for list in ["1k 0", "-1k", "1e40"] {
+            let settings = ParametricSettings {
+                element: "C1".to_string(),
+                list: list.to_string(),
+                ..Default::default()
+            };
+            assert!(parametric_sweep(&netlist, &settings, |_| unreachable!()).is_err());
+        }
+    }
+}
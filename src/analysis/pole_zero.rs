@@ -0,0 +1,315 @@
+use crate::circuit::{ElementType, Netlist};
+
+use super::{
+    ac::laplace_system,
+    complex::Complex,
+    mna::node_voltage,
+    transfer_function::{resolve_settings, TransferFunctionSettings},
+    AnalysisError,
+};
+
+/// Coefficients below this fraction of the largest one are rounding noise
+const COEFFICIENT_TOLERANCE: f64 = 1e-9;
+/// Poles and zeros closer than this fraction of their size cancel each other
+const CANCEL_TOLERANCE: f64 = 1e-6;
+const MAX_ROOT_ITERATIONS: usize = 1000;
+
+/// Poles and zeros of the transfer function from the input source to the output node pair
+#[derive(Clone, Debug)]
+pub struct PoleZeroResult {
+    pub poles: Vec<Complex>,
+    pub zeros: Vec<Complex>,
+}
+
+/// Natural frequency of a pole or zero in Hz
+pub fn natural_frequency(root: Complex) -> f64 {
+    root.abs() / (2.0 * std::f64::consts::PI)
+}
+
+/// Quality factor of a complex pair, `None` for real roots
+pub fn quality_factor(root: Complex) -> Option<f64> {
+    (root.im != 0.0).then(|| root.abs() / (-2.0 * root.re))
+}
+
+/// Finds the poles and zeros of the linear circuit with every source but the input zeroed.
+/// The system determinant D(s) and the numerator N(s) = D(s) H(s) are polynomials of at most
+/// the number of capacitors and inductors in degree, they are sampled on a circle around the
+/// origin and their coefficients recovered with a discrete Fourier transform.
+pub fn pole_zero(
+    netlist: &Netlist,
+    settings: &TransferFunctionSettings,
+) -> Result<PoleZeroResult, AnalysisError> {
+    let (input, positive, negative) = resolve_settings(netlist, settings)?;
+    let degree = netlist
+        .elements
+        .iter()
+        .filter(|x| {
+            matches!(
+                x.element,
+                ElementType::Capacitor(_) | ElementType::Inductor(_)
+            )
+        })
+        .count();
+    let scale = frequency_scale(netlist);
+
+    let count = degree + 1;
+    let mut denominator = Vec::with_capacity(count);
+    let mut numerator = Vec::with_capacity(count);
+    for index in 0..count {
+        let point = unit_root(index as f64, count);
+        let system = laplace_system(
+            netlist,
+            input,
+            Complex::from(1.0),
+            point * Complex::from(scale),
+        );
+        let (solution, determinant) = system.solve_with_determinant()?;
+        let output = node_voltage(&solution, positive) - node_voltage(&solution, negative);
+        denominator.push(determinant);
+        numerator.push(determinant * output);
+    }
+
+    let mut poles = polynomial_roots(&coefficients(&denominator));
+    let mut zeros = polynomial_roots(&coefficients(&numerator));
+    cancel(&mut poles, &mut zeros);
+
+    // Back from the scaled frequency p = s / scale
+    let unscale = |roots: Vec<Complex>| {
+        let mut roots = roots
+            .into_iter()
+            .map(|x| x * Complex::from(scale))
+            .collect::<Vec<Complex>>();
+        roots.sort_by(|a, b| a.abs().total_cmp(&b.abs()).then(a.im.total_cmp(&b.im)));
+        roots
+    };
+
+    Ok(PoleZeroResult {
+        poles: unscale(poles),
+        zeros: unscale(zeros),
+    })
+}
+
+/// Typical angular frequency of the circuit, so the scaled polynomial coefficients stay close to
+/// one. It is the geometric mean of 1/RC and R/L with R the geometric mean of the resistors.
+fn frequency_scale(netlist: &Netlist) -> f64 {
+    let log_mean = |values: &[f64]| match values.len() {
+        0 => None,
+        count => Some(values.iter().map(|x| x.ln()).sum::<f64>() / count as f64),
+    };
+
+    let resistances = netlist
+        .elements
+        .iter()
+        .filter_map(|x| match x.element {
+            ElementType::Resistor(resistance) if resistance > 0.0 => Some(resistance as f64),
+            _ => None,
+        })
+        .collect::<Vec<f64>>();
+    let resistance = log_mean(&resistances).map_or(1.0, f64::exp);
+
+    let frequencies = netlist
+        .elements
+        .iter()
+        .filter_map(|x| match x.element {
+            ElementType::Capacitor(capacitance) if capacitance > 0.0 => {
+                Some(1.0 / (resistance * capacitance as f64))
+            }
+            ElementType::Inductor(inductance) if inductance > 0.0 => {
+                Some(resistance / inductance as f64)
+            }
+            _ => None,
+        })
+        .collect::<Vec<f64>>();
+
+    log_mean(&frequencies).map_or(1.0, f64::exp)
+}
+
+/// `e^(2 pi i index / count)`
+fn unit_root(index: f64, count: usize) -> Complex {
+    Complex::from_polar(1.0, 2.0 * std::f64::consts::PI * index / count as f64)
+}
+
+/// Polynomial coefficients, lowest power first, of the samples taken at the unit roots
+fn coefficients(samples: &[Complex]) -> Vec<Complex> {
+    let count = samples.len();
+    (0..count)
+        .map(|power| {
+            let sum = samples
+                .iter()
+                .enumerate()
+                .map(|(index, sample)| *sample * unit_root(-((index * power) as f64), count))
+                .fold(Complex::from(0.0), |a, b| a + b);
+            Complex::new(sum.re / count as f64, sum.im / count as f64)
+        })
+        .collect()
+}
+
+/// Roots of the polynomial with coefficients lowest power first, found with the Durand-Kerner
+/// iteration. Coefficients that are rounding noise are dropped first, so a vanishing constant
+/// term gives a root at the origin and vanishing high terms lower the degree.
+fn polynomial_roots(coefficients: &[Complex]) -> Vec<Complex> {
+    let largest = coefficients.iter().map(|x| x.abs()).fold(0.0, f64::max);
+    if largest == 0.0 {
+        return vec![];
+    }
+    let significant = |x: &Complex| x.abs() > largest * COEFFICIENT_TOLERANCE;
+    let low = coefficients.iter().position(significant).unwrap_or(0);
+    let high = coefficients.iter().rposition(significant).unwrap_or(0);
+
+    let mut roots = vec![Complex::from(0.0); low];
+    let coefficients = &coefficients[low..=high];
+    let degree = coefficients.len() - 1;
+    if degree == 0 {
+        return roots;
+    }
+
+    let leading = coefficients[degree];
+    let monic = coefficients
+        .iter()
+        .map(|x| *x / leading)
+        .collect::<Vec<Complex>>();
+    let evaluate = |x: Complex| {
+        monic
+            .iter()
+            .rev()
+            .fold(Complex::from(0.0), |sum, coefficient| {
+                sum * x + *coefficient
+            })
+    };
+
+    let seed = Complex::new(0.4, 0.9);
+    let mut estimates = vec![Complex::from(1.0); degree];
+    for index in 1..degree {
+        estimates[index] = estimates[index - 1] * seed;
+    }
+    for _ in 0..MAX_ROOT_ITERATIONS {
+        let mut change: f64 = 0.0;
+        for index in 0..degree {
+            let estimate = estimates[index];
+            let denominator = estimates
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .fold(Complex::from(1.0), |product, (_, x)| {
+                    product * (estimate - *x)
+                });
+            let step = evaluate(estimate) / denominator;
+            estimates[index] = estimate - step;
+            change = change.max(step.abs() / estimate.abs().max(1.0));
+        }
+        if change < 1e-14 {
+            break;
+        }
+    }
+
+    // Conjugate pairs of a real polynomial leave a little imaginary noise on real roots
+    roots.extend(
+        estimates
+            .into_iter()
+            .map(|x| match x.im.abs() < 1e-9 * x.abs() {
+                true => Complex::new(x.re, 0.0),
+                false => x,
+            }),
+    );
+
+    roots
+}
+
+/// Removes pole and zero pairs at the same place, such as the modes of parts of the circuit the
+/// output can not see
+fn cancel(poles: &mut Vec<Complex>, zeros: &mut Vec<Complex>) {
+    let mut index = 0;
+    while index < poles.len() {
+        let pole = poles[index];
+        let tolerance = CANCEL_TOLERANCE * pole.abs().max(1e-3);
+        match zeros.iter().position(|x| (*x - pole).abs() < tolerance) {
+            Some(zero) => {
+                zeros.remove(zero);
+                poles.remove(index);
+            }
+            None => index += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{complex::Complex, element, transfer_function::TransferFunctionSettings},
+        circuit::{ElementType, Netlist},
+    };
+
+    use super::{pole_zero, polynomial_roots, quality_factor};
+
+    fn settings(output: &str) -> TransferFunctionSettings {
+        TransferFunctionSettings {
+            input: "V1".to_string(),
+            output_positive: output.to_string(),
+            output_negative: String::new(),
+        }
+    }
+
+    #[test]
+    fn roots() {
+        // (x - 1)(x - 2)(x + 3) = x^3 - 7x + 6
+        let coefficients = [6.0, -7.0, 0.0, 1.0].map(Complex::from);
+        let mut roots = polynomial_roots(&coefficients)
+            .iter()
+            .map(|x| x.re)
+            .collect::<Vec<f64>>();
+        roots.sort_by(f64::total_cmp);
+        for (root, expected) in roots.iter().zip([-3.0, 1.0, 2.0]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+
+        // Vanishing constant and leading terms, x^2 + x
+        let coefficients = [0.0, 1.0, 1.0, 1e-20].map(Complex::from);
+        assert_eq!(polynomial_roots(&coefficients).len(), 2);
+    }
+
+    #[test]
+    fn series_rlc() {
+        // Output across the capacitor, w0 = 10k rad/s and Q = 1
+        let netlist = Netlist {
+            node_count: 4,
+            node_labels: vec![None; 4],
+            elements: vec![
+                element("V1", ElementType::DCVoltageSource(1.0), 1, 0),
+                element("R1", ElementType::Resistor(100.0), 1, 2),
+                element("L1", ElementType::Inductor(10e-3), 2, 3),
+                element("C1", ElementType::Capacitor(1e-6), 3, 0),
+            ],
+        };
+
+        let result = pole_zero(&netlist, &settings("V3")).unwrap();
+        assert!(result.zeros.is_empty());
+        assert_eq!(result.poles.len(), 2);
+        for pole in result.poles.iter() {
+            assert!((pole.re + 5000.0).abs() < 1e-2);
+            assert!((pole.im.abs() - 5000.0 * 3f64.sqrt()).abs() < 1e-2);
+            assert!((pole.abs() - 1e4).abs() < 1e-2);
+            assert!((quality_factor(*pole).unwrap() - 1.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn rc_high_pass() {
+        // Output across the resistor, zero at the origin and pole at -1/RC
+        let netlist = Netlist {
+            node_count: 3,
+            node_labels: vec![None; 3],
+            elements: vec![
+                element("V1", ElementType::DCVoltageSource(1.0), 1, 0),
+                element("C1", ElementType::Capacitor(1e-6), 1, 2),
+                element("R1", ElementType::Resistor(1000.0), 2, 0),
+            ],
+        };
+
+        let result = pole_zero(&netlist, &settings("V2")).unwrap();
+        assert_eq!(result.zeros.len(), 1);
+        assert!(result.zeros[0].abs() < 1e-3);
+        assert_eq!(result.poles.len(), 1);
+        assert!((result.poles[0] - Complex::from(-1000.0)).abs() < 1e-3);
+        assert_eq!(quality_factor(result.poles[0]), None);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::circuit::Netlist;
+
+use super::{unknown_names, AnalysisError};
+
+/// Relative step of the element values for the central differences
+const RELATIVE_STEP: f64 = 1e-3;
+
+#[derive(Clone)]
+pub struct SensitivitySettings {
+    /// Node voltage or branch current name, empty picks the first node voltage
+    pub output: String,
+    /// How many of the largest contributors are highlighted on the schematic
+    pub highlighted: usize,
+}
+
+impl Default for SensitivitySettings {
+    fn default() -> Self {
+        Self {
+            output: String::new(),
+            highlighted: 3,
+        }
+    }
+}
+
+/// Sensitivity of the output to one element value
+#[derive(Clone, Debug)]
+pub struct Sensitivity {
+    /// Element id in the schematic
+    pub id: u32,
+    pub name: String,
+    /// Unit symbol of the element value
+    pub unit: &'static str,
+    /// Change of the output per unit of element value
+    pub absolute: f64,
+    /// Relative change of the output per relative change of the element value, `None` when the
+    /// output is zero
+    pub normalized: Option<f64>,
+}
+
+impl Sensitivity {
+    /// Magnitude the sensitivities are ranked by
+    pub fn magnitude(&self) -> f64 {
+        self.normalized.unwrap_or(self.absolute).abs()
+    }
+}
+
+#[derive(Clone)]
+pub struct SensitivityResult {
+    pub output: String,
+    pub output_unit: &'static str,
+    /// Output value at the operating point
+    pub output_value: f64,
+    /// Every element with a value, largest first
+    pub sensitivities: Vec<Sensitivity>,
+}
+
+/// DC sensitivity of the unknown named `output`, such as `V(out)` or `I(V1)`, to every element
+/// value. The derivatives are central differences of the operating point solved by
+/// `operating_point`, ranked by normalized magnitude. The differences are taken over a relative
+/// step of 1e-3, so `operating_point` has to solve in f64 for small contributors to rank right.
+pub fn sensitivity(
+    netlist: &Netlist,
+    output: &str,
+    mut operating_point: impl FnMut(&Netlist) -> Result<Vec<f64>, AnalysisError>,
+) -> Result<SensitivityResult, AnalysisError> {
+    let names = unknown_names(netlist);
+    let Some(index) = names.iter().position(|(name, _)| name == output) else {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "{:?} is not a node voltage or branch current",
+            output
+        )));
+    };
+    let mut solve = |netlist: &Netlist| {
+        operating_point(netlist)?
+            .get(index)
+            .copied()
+            .ok_or_else(|| {
+                AnalysisError::Solver("the operating point is missing values".to_string())
+            })
+    };
+    let output_value = solve(netlist)?;
+
+    let mut sensitivities = vec![];
+    let mut perturbed = netlist.clone();
+    for (element_index, netlist_element) in netlist.elements.iter().enumerate() {
+        let Some(nominal) = netlist_element.element.value() else {
+            continue;
+        };
+        let nominal = nominal as f64;
+        let step = if nominal == 0.0 {
+            RELATIVE_STEP
+        } else {
+            nominal.abs() * RELATIVE_STEP
+        };
+
+        // Values are stored as f32, so the step is measured after rounding
+        let mut values = [0.0; 2];
+        let mut outputs = [0.0; 2];
+        for (side, sign) in [1.0, -1.0].into_iter().enumerate() {
+            if let Some(value) = perturbed.elements[element_index].element.value_mut() {
+                *value = (nominal + sign * step) as f32;
+                values[side] = *value as f64;
+            }
+            outputs[side] = solve(&perturbed)?;
+        }
+        if let Some(value) = perturbed.elements[element_index].element.value_mut() {
+            *value = nominal as f32;
+        }
+
+        let absolute = (outputs[0] - outputs[1]) / (values[0] - values[1]);
+        sensitivities.push(Sensitivity {
+            id: netlist_element.id,
+            name: netlist_element.name.clone(),
+            unit: netlist_element
+                .element
+                .display_unit_symbol()
+                .unwrap_or_default(),
+            absolute,
+            normalized: (output_value != 0.0).then(|| absolute * nominal / output_value),
+        });
+    }
+    sensitivities.sort_by(|a, b| b.magnitude().total_cmp(&a.magnitude()));
+
+    Ok(SensitivityResult {
+        output: output.to_string(),
+        output_unit: names[index].1,
+        output_value,
+        sensitivities,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{divider, element, solve_divider, transient::operating_point},
+        circuit::{ElementType, Netlist},
+    };
+
+    use super::sensitivity;
+
+    /// [`divider`] with a capacitor on the output
+    fn divider_with_capacitor() -> Netlist {
+        let mut netlist = divider();
+        netlist
+            .elements
+            .push(element("C1", ElementType::Capacitor(1e-6), 2, 0));
+        netlist
+    }
+
+    #[test]
+    fn divider_sensitivities() {
+        let result = sensitivity(&divider_with_capacitor(), "V(out)", solve_divider).unwrap();
+        assert_eq!(result.output_unit, "V");
+        assert!((result.output_value - 7.5).abs() < 1e-9);
+
+        // The resistors have the same normalized magnitude, the source is ahead of both
+        let names = result
+            .sensitivities
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!((names[0], names[3]), ("V1", "C1"));
+
+        // d/dR1 of V1 R2 / (R1 + R2) is -V1 R2 / (R1 + R2)^2
+        let r1 = result
+            .sensitivities
+            .iter()
+            .find(|x| x.name == "R1")
+            .unwrap();
+        assert!((r1.absolute + 10.0 * 3000.0 / 4000f64.powi(2)).abs() < 1e-6);
+        assert!((r1.normalized.unwrap() + 0.25).abs() < 1e-4);
+        assert!((result.sensitivities[0].normalized.unwrap() - 1.0).abs() < 1e-4);
+        assert_eq!(result.sensitivities[3].absolute, 0.0);
+
+        assert!(sensitivity(&divider_with_capacitor(), "V(in)", solve_divider).is_err());
+    }
+
+    #[test]
+    fn small_contributor_through_solver() {
+        // A 1G load barely changes the divider output
+        let mut netlist = divider();
+        netlist
+            .elements
+            .push(element("R3", ElementType::Resistor(1e9), 2, 0));
+
+        let result = sensitivity(&netlist, "V(out)", operating_point).unwrap();
+        let normalized = |name: &str| {
+            result
+                .sensitivities
+                .iter()
+                .find(|x| x.name == name)
+                .unwrap()
+                .normalized
+                .unwrap()
+        };
+        assert!((normalized("R1") + 0.25).abs() < 1e-6);
+        assert!((normalized("V1") - 1.0).abs() < 1e-9);
+
+        // d ln V / d ln R3 = R1 / (R1 + Rp) * Rp / R3 with Rp = R2 || R3
+        let parallel = 1.0 / (1.0 / 3000.0 + 1.0 / 1e9);
+        let expected = 1000.0 / (1000.0 + parallel) * parallel / 1e9;
+        assert!((normalized("R3") / expected - 1.0).abs() < 1e-3);
+        assert_eq!(result.sensitivities.last().unwrap().name, "R3");
+    }
+}
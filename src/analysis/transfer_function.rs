@@ -0,0 +1,218 @@
+use spice_rs::NodeId;
+
+use crate::circuit::{ElementType, Netlist, NetlistElement};
+
+use super::{mna::node_voltage, unknown_names, AnalysisError};
+
+/// Designator of the test current source driven into the output
+const TEST_SOURCE: &str = "Itf";
+
+#[derive(Clone, Default)]
+pub struct TransferFunctionSettings {
+    /// Designator of the input voltage or current source
+    pub input: String,
+    /// Node voltage name of the positive output node such as `V(out)`
+    pub output_positive: String,
+    /// Node voltage name of the negative output node, empty for ground
+    pub output_negative: String,
+}
+
+/// Small signal DC characteristics between the input source and the output node pair
+#[derive(Clone, Debug)]
+pub struct TransferFunction {
+    /// Output voltage per input volt or amp
+    pub gain: f64,
+    /// `V/V` for a voltage source input and `V/A` for a current source input
+    pub gain_unit: &'static str,
+    /// Resistance seen by the input source
+    pub input_resistance: f64,
+    /// Thevenin resistance between the output nodes
+    pub output_resistance: f64,
+}
+
+/// Computes the `.TF` characteristics of the linear circuit. Every independent source is zeroed,
+/// then the input source is set to 1 for the gain and input resistance, and a 1A test current
+/// into the output gives the output resistance. `operating_point` solves the DC system and
+/// returns the values in [`unknown_names`] order.
+pub fn transfer_function(
+    netlist: &Netlist,
+    settings: &TransferFunctionSettings,
+    mut operating_point: impl FnMut(&Netlist) -> Result<Vec<f64>, AnalysisError>,
+) -> Result<TransferFunction, AnalysisError> {
+    let (input, positive, negative) = resolve_settings(netlist, settings)?;
+
+    let mut zeroed = netlist.clone();
+    for netlist_element in zeroed.elements.iter_mut() {
+        if let ElementType::DCVoltageSource(value) | ElementType::DCCurrentSource(value) =
+            &mut netlist_element.element
+        {
+            *value = 0.0;
+        }
+    }
+
+    let mut driven = zeroed.clone();
+    let input_element = &mut driven.elements[input];
+    let voltage_input = matches!(input_element.element, ElementType::DCVoltageSource(_));
+    if let Some(value) = input_element.element.value_mut() {
+        *value = 1.0;
+    }
+    let (input1, input2) = (input_element.node1.0, input_element.node2.0);
+    let solution = operating_point(&driven)?;
+    let output_voltage =
+        |solution: &[f64]| node_voltage(solution, positive) - node_voltage(solution, negative);
+
+    let gain = output_voltage(&solution);
+    let input_resistance = match voltage_input {
+        // The branch current flows from the positive node through the source, so it is
+        // negative while the source delivers current
+        true => {
+            let branch = input_branch_index(netlist, input);
+            -1.0 / solution.get(branch).copied().unwrap_or_default()
+        }
+        // The source drives its current into its second node
+        false => node_voltage(&solution, input2) - node_voltage(&solution, input1),
+    };
+
+    let mut tested = zeroed;
+    tested.elements.push(NetlistElement {
+        id: u32::MAX,
+        name: TEST_SOURCE.to_string(),
+        element: ElementType::DCCurrentSource(1.0),
+        node1: NodeId(negative),
+        node2: NodeId(positive),
+    });
+    let output_resistance = output_voltage(&operating_point(&tested)?);
+
+    Ok(TransferFunction {
+        gain,
+        gain_unit: if voltage_input { "V/V" } else { "V/A" },
+        input_resistance,
+        output_resistance,
+    })
+}
+
+/// Netlist index of the input source and node numbers of the positive and negative output
+pub fn resolve_settings(
+    netlist: &Netlist,
+    settings: &TransferFunctionSettings,
+) -> Result<(usize, usize, usize), AnalysisError> {
+    let input = netlist
+        .elements
+        .iter()
+        .position(|x| {
+            x.name.eq_ignore_ascii_case(&settings.input)
+                && matches!(
+                    x.element,
+                    ElementType::DCVoltageSource(_) | ElementType::DCCurrentSource(_)
+                )
+        })
+        .ok_or_else(|| {
+            AnalysisError::InvalidSettings(format!(
+                "{:?} is not a voltage or current source",
+                settings.input
+            ))
+        })?;
+    let positive = node_index(netlist, &settings.output_positive)?;
+    let negative = node_index(netlist, &settings.output_negative)?;
+    if positive == negative {
+        return Err(AnalysisError::InvalidSettings(
+            "the output nodes must differ".to_string(),
+        ));
+    }
+
+    Ok((input, positive, negative))
+}
+
+/// Node number of a node voltage name, empty or `0` for ground
+fn node_index(netlist: &Netlist, name: &str) -> Result<usize, AnalysisError> {
+    if name.is_empty() || name == "0" {
+        return Ok(0);
+    }
+
+    unknown_names(netlist)
+        .iter()
+        .take(netlist.node_count.saturating_sub(1))
+        .position(|(x, _)| x == name)
+        .map(|x| x + 1)
+        .ok_or_else(|| AnalysisError::InvalidSettings(format!("{:?} is not a node", name)))
+}
+
+/// Index of the branch current of the voltage source at `element` in the solution
+fn input_branch_index(netlist: &Netlist, element: usize) -> usize {
+    let branch = netlist.stamps()[element]
+        .and_then(|x| x.branch())
+        .unwrap_or_default();
+
+    netlist.node_count - 1 + branch
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{
+            divider, element,
+            transient::{transient, InitialConditions, TransientSettings},
+            AnalysisError,
+        },
+        circuit::{ElementType, Netlist},
+    };
+
+    use super::{transfer_function, TransferFunctionSettings};
+
+    /// First point of a transient run, which is the operating point
+    fn operating_point(netlist: &Netlist) -> Result<Vec<f64>, AnalysisError> {
+        let settings = TransientSettings {
+            stop_time: 1e-9,
+            time_step: 1e-9,
+            initial_conditions: InitialConditions::OperatingPoint,
+        };
+
+        transient(netlist, &settings).map(|x| x.traces.iter().map(|x| x.values[0]).collect())
+    }
+
+    /// [`divider`] with the current source I1 driving the output
+    fn driven_divider() -> Netlist {
+        let mut netlist = divider();
+        netlist
+            .elements
+            .push(element("I1", ElementType::DCCurrentSource(2e-3), 0, 2));
+        netlist
+    }
+
+    #[test]
+    fn voltage_divider() {
+        let settings = TransferFunctionSettings {
+            input: "v1".to_string(),
+            output_positive: "V(out)".to_string(),
+            output_negative: String::new(),
+        };
+
+        let result = transfer_function(&driven_divider(), &settings, operating_point).unwrap();
+        assert_eq!(result.gain_unit, "V/V");
+        assert!((result.gain - 0.75).abs() < 1e-6);
+        assert!((result.input_resistance - 4000.0).abs() < 1e-3);
+        assert!((result.output_resistance - 750.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn current_input() {
+        let settings = TransferFunctionSettings {
+            input: "I1".to_string(),
+            output_positive: "V(out)".to_string(),
+            output_negative: "0".to_string(),
+        };
+
+        // The voltage source is shorted, so the current source sees 1k parallel to 3k
+        let result = transfer_function(&driven_divider(), &settings, operating_point).unwrap();
+        assert_eq!(result.gain_unit, "V/A");
+        assert!((result.gain - 750.0).abs() < 1e-3);
+        assert!((result.input_resistance - 750.0).abs() < 1e-3);
+
+        let settings = TransferFunctionSettings {
+            output_positive: "V(out)".to_string(),
+            output_negative: "V(out)".to_string(),
+            ..settings
+        };
+        assert!(transfer_function(&driven_divider(), &settings, operating_point).is_err());
+    }
+}
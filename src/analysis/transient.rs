@@ -0,0 +1,389 @@
+use crate::circuit::{Netlist, Stamp};
+
+use super::{
+    mna::{node_voltage, Mna},
+    unknown_names, AnalysisError, SimulationResult, Trace,
+};
+
+/// Upper bound on time points so a typo in the settings can not exhaust memory
+const MAX_TIME_POINTS: f64 = 1_000_000.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum InitialConditions {
+    /// Start from the DC operating point, capacitors open and inductors shorted
+    OperatingPoint,
+    /// Start with discharged capacitors and no inductor current
+    Zero,
+}
+
+#[derive(Clone, Copy)]
+pub struct TransientSettings {
+    pub stop_time: f64,
+    pub time_step: f64,
+    pub initial_conditions: InitialConditions,
+}
+
+impl Default for TransientSettings {
+    fn default() -> Self {
+        Self {
+            stop_time: 1e-3,
+            time_step: 1e-6,
+            initial_conditions: InitialConditions::OperatingPoint,
+        }
+    }
+}
+
+/// Voltage across and current through every capacitor and inductor at the last time point
+#[derive(Clone, Copy, Default)]
+struct ReactiveState {
+    voltage: f64,
+    current: f64,
+}
+
+enum Step {
+    Initial(InitialConditions),
+    BackwardEuler(f64),
+    Trapezoidal(f64),
+}
+
+/// Fixed step time domain integration, backward Euler for the first step and trapezoidal after
+pub fn transient(
+    netlist: &Netlist,
+    settings: &TransientSettings,
+) -> Result<SimulationResult, AnalysisError> {
+    if settings.time_step <= 0.0 || settings.stop_time <= 0.0 {
+        return Err(AnalysisError::InvalidSettings(
+            "stop time and time step must be positive".to_string(),
+        ));
+    }
+    let step_count = (settings.stop_time / settings.time_step).ceil();
+    if step_count > MAX_TIME_POINTS {
+        return Err(AnalysisError::InvalidSettings(format!(
+            "more than {} time points",
+            MAX_TIME_POINTS
+        )));
+    }
+    let step_count = step_count as usize;
+
+    let names = unknown_names(netlist);
+    let mut traces = names
+        .iter()
+        .map(|(name, unit)| Trace {
+            name: name.clone(),
+            unit,
+            values: Vec::with_capacity(step_count + 1),
+        })
+        .collect::<Vec<Trace>>();
+    let mut sweep = Vec::with_capacity(step_count + 1);
+
+    let mut states = vec![ReactiveState::default(); netlist.elements.len()];
+    let solution = solve_step(
+        netlist,
+        &mut states,
+        Step::Initial(settings.initial_conditions),
+    )?;
+    sweep.push(0.0);
+    for (trace, value) in traces.iter_mut().zip(solution.iter()) {
+        trace.values.push(*value);
+    }
+
+    for index in 1..=step_count {
+        let time = (index as f64 * settings.time_step).min(settings.stop_time);
+        let time_step = time - sweep.last().unwrap();
+        let step = match index {
+            1 => Step::BackwardEuler(time_step),
+            _ => Step::Trapezoidal(time_step),
+        };
+
+        let solution = solve_step(netlist, &mut states, step)?;
+        sweep.push(time);
+        for (trace, value) in traces.iter_mut().zip(solution.iter()) {
+            trace.values.push(*value);
+        }
+    }
+
+    Ok(SimulationResult {
+        title: "Transient".to_string(),
+        sweep_name: "Time".to_string(),
+        sweep_unit: "s",
+        log_sweep: false,
+        sweep,
+        traces,
+    })
+}
+
+/// DC operating point with capacitors open and inductors shorted, solved in f64 so small changes
+/// such as the differences of a sensitivity analysis keep their precision. Values are in
+/// [`unknown_names`] order.
+pub fn operating_point(netlist: &Netlist) -> Result<Vec<f64>, AnalysisError> {
+    let mut states = vec![ReactiveState::default(); netlist.elements.len()];
+
+    solve_step(
+        netlist,
+        &mut states,
+        Step::Initial(InitialConditions::OperatingPoint),
+    )
+}
+
+/// Stamps every element with its model for `step`, solves and updates the reactive states
+fn solve_step(
+    netlist: &Netlist,
+    states: &mut [ReactiveState],
+    step: Step,
+) -> Result<Vec<f64>, AnalysisError> {
+    let stamps = netlist.stamps();
+    let branches = netlist.branch_count();
+    // Capacitors with a fixed initial voltage need a branch of their own
+    let capacitor_branches = match step {
+        Step::Initial(InitialConditions::Zero) => stamps
+            .iter()
+            .filter(|x| matches!(x, Some(Stamp::Capacitor(_))))
+            .count(),
+        _ => 0,
+    };
+    let mut mna = Mna::new(netlist.node_count, branches + capacitor_branches);
+
+    let mut capacitor_branch = branches;
+    // Capacitor companion models as (conductance, equivalent current)
+    let mut companions = vec![(0.0, 0.0); netlist.elements.len()];
+    for (index, (netlist_element, stamp)) in netlist.elements.iter().zip(&stamps).enumerate() {
+        let node1 = netlist_element.node1.0;
+        let node2 = netlist_element.node2.0;
+        let state = states[index];
+
+        match *stamp {
+            Some(Stamp::Resistor(resistance)) => {
+                mna.stamp_conductance(node1, node2, 1.0 / resistance);
+            }
+            Some(Stamp::VoltageSource(voltage, branch)) => {
+                mna.stamp_branch(node1, node2, branch, 0.0, voltage);
+            }
+            Some(Stamp::CurrentSource(amps)) => {
+                mna.stamp_current(node1, node2, amps);
+            }
+            Some(Stamp::Capacitor(capacitance)) => {
+                let companion = match step {
+                    Step::Initial(InitialConditions::OperatingPoint) => (0.0, 0.0),
+                    Step::Initial(InitialConditions::Zero) => {
+                        mna.stamp_branch(node1, node2, capacitor_branch, 0.0, 0.0);
+                        capacitor_branch += 1;
+                        (0.0, 0.0)
+                    }
+                    Step::BackwardEuler(time_step) => {
+                        let conductance = capacitance / time_step;
+                        (conductance, conductance * state.voltage)
+                    }
+                    Step::Trapezoidal(time_step) => {
+                        let conductance = 2.0 * capacitance / time_step;
+                        (conductance, conductance * state.voltage + state.current)
+                    }
+                };
+
+                mna.stamp_conductance(node1, node2, companion.0);
+                mna.stamp_current(node2, node1, companion.1);
+                companions[index] = companion;
+            }
+            Some(Stamp::Inductor(inductance, branch)) => match step {
+                Step::Initial(InitialConditions::OperatingPoint) => {
+                    mna.stamp_branch(node1, node2, branch, 0.0, 0.0);
+                }
+                Step::Initial(InitialConditions::Zero) => {
+                    mna.stamp_branch_current(node1, node2, branch, 0.0);
+                }
+                Step::BackwardEuler(time_step) => {
+                    let resistance = inductance / time_step;
+                    mna.stamp_branch(
+                        node1,
+                        node2,
+                        branch,
+                        resistance,
+                        -resistance * state.current,
+                    );
+                }
+                Step::Trapezoidal(time_step) => {
+                    let resistance = 2.0 * inductance / time_step;
+                    mna.stamp_branch(
+                        node1,
+                        node2,
+                        branch,
+                        resistance,
+                        -resistance * state.current - state.voltage,
+                    );
+                }
+            },
+            None => (),
+        }
+    }
+
+    let mut solution = mna.solve()?;
+
+    let mut capacitor_branch = branches;
+    for (index, (netlist_element, stamp)) in netlist.elements.iter().zip(&stamps).enumerate() {
+        let voltage = node_voltage(&solution, netlist_element.node1.0)
+            - node_voltage(&solution, netlist_element.node2.0);
+
+        match *stamp {
+            Some(Stamp::Capacitor(_)) => {
+                let current = match step {
+                    Step::Initial(InitialConditions::Zero) => {
+                        capacitor_branch += 1;
+                        solution[mna_branch_index(netlist, capacitor_branch - 1)]
+                    }
+                    _ => companions[index].0 * voltage - companions[index].1,
+                };
+                states[index] = ReactiveState { voltage, current };
+            }
+            Some(Stamp::Inductor(_, branch)) => {
+                states[index] = ReactiveState {
+                    voltage,
+                    current: solution[mna_branch_index(netlist, branch)],
+                };
+            }
+            _ => (),
+        }
+    }
+
+    // Only report the unknowns every analysis shares
+    solution.truncate(netlist.node_count.saturating_sub(1) + branches);
+
+    Ok(solution)
+}
+
+fn mna_branch_index(netlist: &Netlist, branch: usize) -> usize {
+    netlist.node_count.saturating_sub(1) + branch
+}
+
+#[cfg(test)]
+mod tests {
+    use spice_rs::runners::dc_op::dc_op;
+
+    use crate::{
+        analysis::{divider, element, rc_low_pass, AnalysisError},
+        circuit::{ElementType, Netlist},
+    };
+
+    use super::{operating_point, transient, InitialConditions, TransientSettings};
+
+    fn value_at(values: &[f64], sweep: &[f64], time: f64) -> f64 {
+        let index = sweep.iter().position(|x| *x >= time - 1e-12).unwrap();
+        values[index]
+    }
+
+    #[test]
+    fn rc_charging() {
+        let settings = TransientSettings {
+            stop_time: 5e-3,
+            time_step: 1e-5,
+            initial_conditions: InitialConditions::Zero,
+        };
+
+        let result = transient(&rc_low_pass(), &settings).unwrap();
+        let capacitor_voltage = &result.traces[1];
+        assert_eq!(capacitor_voltage.name, "V2");
+        assert_eq!(result.sweep.len(), 501);
+        assert!(capacitor_voltage.values[0].abs() < 1e-9);
+
+        let expected = 1.0 - (-1.0f64).exp();
+        let actual = value_at(&capacitor_voltage.values, &result.sweep, 1e-3);
+        assert!((actual - expected).abs() < 1e-3, "{}", actual);
+    }
+
+    #[test]
+    fn rl_current() {
+        // 1V source, 1 ohm into node 2, 1mH to ground. Time constant of 1ms.
+        let netlist = Netlist {
+            node_count: 3,
+            node_labels: vec![None; 3],
+            elements: vec![
+                element("V1", ElementType::DCVoltageSource(1.0), 1, 0),
+                element("R1", ElementType::Resistor(1.0), 1, 2),
+                element("L1", ElementType::Inductor(1e-3), 2, 0),
+            ],
+        };
+        let settings = TransientSettings {
+            stop_time: 3e-3,
+            time_step: 1e-5,
+            initial_conditions: InitialConditions::Zero,
+        };
+
+        let result = transient(&netlist, &settings).unwrap();
+        let inductor_current = &result.traces[3];
+        assert_eq!(inductor_current.name, "I(L1)");
+        assert!(inductor_current.values[0].abs() < 1e-9);
+
+        let expected = 1.0 - (-1.0f64).exp();
+        let actual = value_at(&inductor_current.values, &result.sweep, 1e-3);
+        assert!((actual - expected).abs() < 1e-3, "{}", actual);
+    }
+
+    #[test]
+    fn operating_point_start_is_steady() {
+        let settings = TransientSettings {
+            stop_time: 1e-3,
+            time_step: 1e-5,
+            initial_conditions: InitialConditions::OperatingPoint,
+        };
+
+        let result = transient(&rc_low_pass(), &settings).unwrap();
+        assert!(result.traces[1]
+            .values
+            .iter()
+            .all(|x| (x - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn operating_point_matches_spice() {
+        // 1mA pulled out of the divider output
+        let mut netlist = divider();
+        netlist
+            .elements
+            .push(element("I1", ElementType::DCCurrentSource(1e-3), 2, 0));
+
+        let solution = operating_point(&netlist).unwrap();
+        let spice = dc_op(&netlist.circuit()).unwrap();
+        assert_eq!(solution.len(), spice.len());
+        for (value, expected) in solution.iter().zip(spice.iter()) {
+            let expected = *expected as f64;
+            assert!(
+                (value - expected).abs() < 1e-4 * expected.abs().max(1e-3),
+                "{} {}",
+                value,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn zero_resistance() {
+        let netlist = Netlist {
+            node_count: 3,
+            node_labels: vec![None; 3],
+            elements: vec![
+                element("V1", ElementType::DCVoltageSource(1.0), 1, 0),
+                element("R1", ElementType::Resistor(0.0), 1, 2),
+                element("R2", ElementType::Resistor(1000.0), 2, 0),
+            ],
+        };
+
+        assert!(matches!(
+            transient(&netlist, &TransientSettings::default()),
+            Err(AnalysisError::InvalidSettings(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let netlist = Netlist {
+            node_count: 1,
+            node_labels: vec![None],
+            elements: vec![],
+        };
+        let settings = TransientSettings {
+            stop_time: 1e-3,
+            time_step: 0.0,
+            initial_conditions: InitialConditions::Zero,
+        };
+
+        assert!(transient(&netlist, &settings).is_err());
+    }
+}
@@ -1,40 +1,210 @@
+use std::path::PathBuf;
+
+use egui::{Pos2, Vec2};
+
 use crate::{
-    circuit::{GuiCircuit, ToPlaceElement},
+    analysis::{
+        ac::AcSettings, dc_sweep::DcSweepSettings, monte_carlo::MonteCarloSettings,
+        parametric::ParametricSettings, sensitivity::SensitivitySettings,
+        transfer_function::TransferFunctionSettings, transient::TransientSettings,
+        SimulationResult,
+    },
+    canvas::{view_shortcuts, CanvasView},
+    circuit::{GuiCircuit, ToPlaceElement, WireSegment},
+    clipboard::handle_clipboard,
     element_pointer::handle_elements,
     element_properties::handle_selected_object_properties,
+    erc::ErcReport,
+    history::History,
     menu_bar::menu_bar,
+    monte_carlo_window::{handle_monte_carlo_window, MonteCarloView},
+    operating_point::OperatingPoint,
+    plot_panel::{handle_plot_panel, PlotState},
+    pole_zero_window::{handle_pole_zero_window, PoleZeroView},
+    runner_windows::{handle_runner_windows, RunnerWindow, SensitivityReport},
     terminal::handle_terminal,
     utils::ipos2::IPos2,
 };
 
 pub struct SpiceGuIrsApp {
     pub gui_circuit: GuiCircuit,
+    pub canvas: CanvasView,
     pub to_place_element: Option<ToPlaceElement>,
-    pub selected_element: Option<u32>,
-    pub selected_node: Option<IPos2>,
+    pub selected_elements: Vec<u32>,
+    pub selected_wires: Vec<WireSegment>,
+    /// Index into the circuit's net labels
+    pub selected_net_label: Option<usize>,
+    /// Clicking a wire places a net label instead of selecting it
+    pub placing_net_label: bool,
+    /// Clicking a wire point adds or removes a junction dot instead of selecting the wire
+    pub placing_junction: bool,
     pub drag_data: Option<DragData>,
+    pub element_drag: Option<ElementDrag>,
+    /// Corner the rubber band selection box was started from
+    pub marquee_start: Option<Pos2>,
     pub terminal_lines: Vec<String>,
+    /// Schematic text of the last copy, pasted from the Edit menu
+    pub clipboard: Option<String>,
+    /// File the current schematic was last opened from or saved to
+    pub schematic_path: Option<PathBuf>,
+    pub history: History,
+    pub runner_window: Option<RunnerWindow>,
+    pub transient_settings: TransientSettings,
+    pub ac_settings: AcSettings,
+    pub dc_sweep_settings: DcSweepSettings,
+    pub parametric_settings: ParametricSettings,
+    pub monte_carlo_settings: MonteCarloSettings,
+    pub sensitivity_settings: SensitivitySettings,
+    pub transfer_function_settings: TransferFunctionSettings,
+    pub pole_zero_settings: TransferFunctionSettings,
+    /// Result of the last analysis run
+    pub simulation_result: Option<SimulationResult>,
+    pub plot: PlotState,
+    /// Values of the last DC operating point, shown on the schematic until the next edit
+    pub operating_point: Option<OperatingPoint>,
+    pub show_operating_point: bool,
+    /// Statistics of the last Monte Carlo run
+    pub monte_carlo: Option<MonteCarloView>,
+    /// Poles and zeros of the last pole-zero run
+    pub pole_zero: Option<PoleZeroView>,
+    /// Result of the last electrical rule check, highlighted until the next edit
+    pub erc_report: Option<ErcReport>,
+    /// Result of the last sensitivity run, highlighted until the next edit
+    pub sensitivity: Option<SensitivityReport>,
 }
 
 impl SpiceGuIrsApp {
     pub fn new() -> Self {
         Self {
             gui_circuit: GuiCircuit::default(),
+            canvas: CanvasView::default(),
             to_place_element: None,
-            selected_element: None,
-            selected_node: None,
+            selected_elements: vec![],
+            selected_wires: vec![],
+            selected_net_label: None,
+            placing_net_label: false,
+            placing_junction: false,
             drag_data: None,
+            element_drag: None,
+            marquee_start: None,
             terminal_lines: vec![],
+            clipboard: None,
+            schematic_path: None,
+            history: History::default(),
+            runner_window: None,
+            transient_settings: TransientSettings::default(),
+            ac_settings: AcSettings::default(),
+            dc_sweep_settings: DcSweepSettings::default(),
+            parametric_settings: ParametricSettings::default(),
+            monte_carlo_settings: MonteCarloSettings::default(),
+            sensitivity_settings: SensitivitySettings::default(),
+            transfer_function_settings: TransferFunctionSettings::default(),
+            pole_zero_settings: TransferFunctionSettings::default(),
+            simulation_result: None,
+            plot: PlotState::default(),
+            operating_point: None,
+            show_operating_point: true,
+            monte_carlo: None,
+            pole_zero: None,
+            erc_report: None,
+            sensitivity: None,
         }
     }
+
+    pub fn clear_selection(&mut self) {
+        self.selected_elements.clear();
+        self.selected_wires.clear();
+        self.selected_net_label = None;
+    }
+
+    pub fn has_selection(&self) -> bool {
+        !self.selected_elements.is_empty()
+            || !self.selected_wires.is_empty()
+            || self.selected_net_label.is_some()
+    }
+
+    /// Removes every selected element, wire segment and net label, the caller records the history
+    pub fn remove_selection(&mut self) {
+        if let Some(index) = self.selected_net_label {
+            self.gui_circuit.net_labels.remove(index);
+        }
+        for id in self.selected_elements.iter() {
+            self.gui_circuit.remove_element(*id);
+            println!("Delete Element");
+        }
+        for segment in self.selected_wires.iter() {
+            self.gui_circuit.remove_wire_segment(*segment);
+        }
+        self.clear_selection();
+    }
+
+    /// Selects an element, `extend` toggles it in the current selection instead of replacing it
+    pub fn select_element(&mut self, id: u32, extend: bool) {
+        if !extend {
+            self.clear_selection();
+        }
+        self.selected_net_label = None;
+
+        match self.selected_elements.iter().position(|x| *x == id) {
+            Some(index) if extend => {
+                self.selected_elements.remove(index);
+            }
+            Some(_) => {}
+            None => self.selected_elements.push(id),
+        }
+    }
+
+    /// Selects a wire segment, `extend` toggles it in the current selection instead of replacing it
+    pub fn select_wire(&mut self, segment: WireSegment, extend: bool) {
+        if !extend {
+            self.clear_selection();
+        }
+        self.selected_net_label = None;
+
+        match self.selected_wires.iter().position(|x| *x == segment) {
+            Some(index) if extend => {
+                self.selected_wires.remove(index);
+            }
+            Some(_) => {}
+            None => self.selected_wires.push(segment),
+        }
+    }
+
+    pub fn select_net_label(&mut self, index: usize) {
+        self.clear_selection();
+        self.selected_net_label = Some(index);
+    }
+
+    /// Drops selections that no longer exist after the circuit was replaced
+    pub fn validate_selection(&mut self) {
+        self.selected_elements
+            .retain(|x| self.gui_circuit.gui_elements.contains_key(x));
+        let segments = self.gui_circuit.wire_segments();
+        self.selected_wires.retain(|x| segments.contains(x));
+        if self
+            .selected_net_label
+            .is_some_and(|x| x >= self.gui_circuit.net_labels.len())
+        {
+            self.selected_net_label = None;
+        }
+        self.drag_data = None;
+        self.element_drag = None;
+        self.marquee_start = None;
+    }
 }
 
 impl eframe::App for SpiceGuIrsApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         menu_bar(self, ctx);
         handle_terminal(self, ctx);
+        handle_plot_panel(self, ctx);
         handle_elements(self, ctx);
+        view_shortcuts(self, ctx);
+        handle_clipboard(self, ctx);
         handle_selected_object_properties(self, ctx);
+        handle_runner_windows(self, ctx);
+        handle_monte_carlo_window(self, ctx);
+        handle_pole_zero_window(self, ctx);
     }
 }
 
@@ -54,3 +224,12 @@ impl DragData {
         }
     }
 }
+
+/// Placed element being dragged to a new position, together with the rest of the selection
+/// when it is selected
+#[derive(Clone, Copy)]
+pub struct ElementDrag {
+    pub id: u32,
+    /// Offset from the pointer to the element center when the drag started
+    pub offset: Vec2,
+}
@@ -0,0 +1,235 @@
+use egui::{emath::TSTransform, Context, Id, Key, LayerId, Order, PointerButton, Pos2, Rect, Ui};
+
+use crate::{app::SpiceGuIrsApp, GRID_SIZE};
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 8.0;
+/// Grid dots closer than this on screen are thinned out to every other one
+const MIN_GRID_SPACING: f32 = 12.0;
+
+/// Layer the schematic is drawn on, transformed from world to screen coordinates
+pub fn canvas_layer() -> LayerId {
+    LayerId::new(Order::Foreground, Id::new("schematic_canvas"))
+}
+
+/// Part of the schematic shown in the central panel. The schematic is stored in world
+/// coordinates, snapping to the grid happens there before anything is transformed to the screen.
+pub struct CanvasView {
+    /// World to screen transform
+    pub transform: TSTransform,
+    /// Screen rect of the central panel in the last frame
+    pub viewport: Rect,
+    /// Middle mouse button or space and left mouse button drag in progress
+    pub panning: bool,
+}
+
+impl Default for CanvasView {
+    fn default() -> Self {
+        Self {
+            transform: TSTransform::IDENTITY,
+            viewport: Rect::ZERO,
+            panning: false,
+        }
+    }
+}
+
+impl CanvasView {
+    pub fn to_world(&self, screen_position: Pos2) -> Pos2 {
+        self.transform.inverse() * screen_position
+    }
+
+    pub fn to_screen(&self, world_position: Pos2) -> Pos2 {
+        self.transform * world_position
+    }
+
+    /// Latest pointer position in world coordinates
+    pub fn pointer_position(&self, ctx: &Context) -> Option<Pos2> {
+        ctx.pointer_latest_pos().map(|x| self.to_world(x))
+    }
+
+    /// World rect visible in the viewport
+    pub fn visible_rect(&self) -> Rect {
+        self.transform.inverse() * self.viewport
+    }
+
+    /// Scales the view by `factor` keeping the world point under `screen_position` in place
+    pub fn zoom_around(&mut self, screen_position: Pos2, factor: f32) {
+        let scaling = (self.transform.scaling * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let world_position = self.to_world(screen_position);
+        self.transform = TSTransform::new(
+            screen_position.to_vec2() - world_position.to_vec2() * scaling,
+            scaling,
+        );
+    }
+
+    /// Centers `rect` in the viewport as large as it fits with a margin of two grid cells
+    pub fn fit_rect(&mut self, rect: Rect) {
+        let rect = rect.expand(2.0 * GRID_SIZE as f32);
+        let scaling = (self.viewport.width() / rect.width())
+            .min(self.viewport.height() / rect.height())
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        self.transform = TSTransform::new(
+            self.viewport.center().to_vec2() - rect.center().to_vec2() * scaling,
+            scaling,
+        );
+    }
+
+    /// Back to one world unit per screen point with the world origin at the viewport corner
+    pub fn reset(&mut self) {
+        self.transform = TSTransform::from_translation(self.viewport.min.to_vec2());
+    }
+}
+
+/// Pans with the middle mouse button or space and left mouse button drag and zooms with the
+/// scroll wheel around the pointer, only while the pointer is over the schematic
+pub fn handle_pan_zoom(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let canvas = &mut app.canvas;
+    canvas.viewport = ui.max_rect();
+
+    let (pressed, released, any_down, delta, hover, scroll, zoom) = ui.input(|x| {
+        let pressed = x.pointer.button_pressed(PointerButton::Middle)
+            || (x.pointer.button_pressed(PointerButton::Primary) && x.key_down(Key::Space));
+        (
+            pressed,
+            x.pointer.any_released(),
+            x.pointer.any_down(),
+            x.pointer.delta(),
+            x.pointer.hover_pos(),
+            x.smooth_scroll_delta,
+            x.zoom_delta(),
+        )
+    });
+    let over_canvas = hover.is_some_and(|pointer| {
+        canvas.viewport.contains(pointer)
+            && ui
+                .ctx()
+                .layer_id_at(pointer)
+                .is_none_or(|x| x == canvas_layer() || x == ui.layer_id())
+    });
+
+    if pressed && over_canvas && !ui.ctx().wants_keyboard_input() {
+        canvas.panning = true;
+    }
+    // Stays set in the release frame so the release does not also place or select anything
+    if !any_down && !released {
+        canvas.panning = false;
+    }
+    if canvas.panning {
+        canvas.transform.translation += delta;
+    }
+
+    if let Some(pointer) = hover.filter(|_| over_canvas) {
+        let factor = zoom * (scroll.y * 0.002).exp();
+        if factor != 1.0 {
+            canvas.zoom_around(pointer, factor);
+        }
+    }
+}
+
+/// Dots on the grid points, thinned out when zoomed far out
+pub fn display_grid(app: &SpiceGuIrsApp, ui: &mut Ui) {
+    let canvas = &app.canvas;
+    let visible = canvas.visible_rect();
+    let mut step = GRID_SIZE;
+    while (step as f32) * canvas.transform.scaling < MIN_GRID_SPACING {
+        step *= 2;
+    }
+
+    let first = |min: f32| (min / step as f32).floor() as i32 * step;
+    let color = ui.visuals().weak_text_color().gamma_multiply(0.5);
+    let painter = ui.painter();
+    for x in (first(visible.min.x)..=visible.max.x.ceil() as i32).step_by(step as usize) {
+        for y in (first(visible.min.y)..=visible.max.y.ceil() as i32).step_by(step as usize) {
+            let position = canvas.to_screen(Pos2::new(x as f32, y as f32));
+            painter.circle_filled(position, 1.0, color);
+        }
+    }
+}
+
+/// Zooms to show the whole schematic, resets the view when it is empty
+pub fn fit_to_content(app: &mut SpiceGuIrsApp) {
+    match app.gui_circuit.bounding_rect() {
+        Some(rect) => app.canvas.fit_rect(rect),
+        None => app.canvas.reset(),
+    }
+}
+
+/// Zooms to show the selected elements, wire segments and net label
+pub fn zoom_to_selection(app: &mut SpiceGuIrsApp) {
+    let circuit = &app.gui_circuit;
+    let rect = app
+        .selected_elements
+        .iter()
+        .filter_map(|x| circuit.gui_elements.get(x))
+        .map(|x| x.rect)
+        .chain(app.selected_wires.iter().map(|x| x.rect()))
+        .chain(
+            app.selected_net_label
+                .and_then(|x| circuit.net_labels.get(x))
+                .map(|x| Rect::from_min_max(x.position.to_pos2(), x.position.to_pos2())),
+        )
+        .reduce(|a, b| a.union(b));
+
+    if let Some(rect) = rect {
+        app.canvas.fit_rect(rect);
+    }
+}
+
+pub fn view_shortcuts(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    if ctx.input(|x| x.key_pressed(Key::Home)) {
+        fit_to_content(app);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Pos2, Rect, Vec2};
+
+    use super::CanvasView;
+
+    #[test]
+    fn zoom_keeps_pointer_in_place() {
+        let mut canvas = CanvasView {
+            viewport: Rect::from_min_size(Pos2::new(100.0, 50.0), Vec2::new(800.0, 600.0)),
+            ..Default::default()
+        };
+        canvas.reset();
+        assert_eq!(
+            canvas.to_world(Pos2::new(132.0, 66.0)),
+            Pos2::new(32.0, 16.0)
+        );
+
+        canvas.zoom_around(Pos2::new(300.0, 200.0), 2.0);
+        let world = canvas.to_world(Pos2::new(300.0, 200.0));
+        assert!((world - Pos2::new(200.0, 150.0)).length() < 1e-3);
+        assert_eq!(canvas.transform.scaling, 2.0);
+
+        canvas.zoom_around(Pos2::new(300.0, 200.0), 1000.0);
+        assert_eq!(canvas.transform.scaling, super::MAX_ZOOM);
+    }
+
+    #[test]
+    fn fit_rect_centers_content() {
+        let mut canvas = CanvasView {
+            viewport: Rect::from_min_size(Pos2::ZERO, Vec2::new(800.0, 400.0)),
+            ..Default::default()
+        };
+        canvas.fit_rect(Rect::from_min_max(
+            Pos2::new(32.0, 32.0),
+            Pos2::new(160.0, 96.0),
+        ));
+
+        // 128 tall with the margin, the height limits the zoom
+        assert!((canvas.transform.scaling - 400.0 / 128.0).abs() < 1e-3);
+        let center = canvas.to_screen(Pos2::new(96.0, 64.0));
+        assert!((center - Pos2::new(400.0, 200.0)).length() < 1e-3);
+        assert!(canvas.visible_rect().contains_rect(Rect::from_min_max(
+            Pos2::new(32.0, 32.0),
+            Pos2::new(160.0, 96.0)
+        )));
+    }
+}
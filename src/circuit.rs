@@ -1,7 +1,6 @@
-use std::collections::VecDeque;
-
-use egui::{Image, Pos2, Rect, Vec2};
+use egui::{Image, ImageSource, Pos2, Rect, Vec2};
 use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
 use spice_rs::{
     elements::{
         capacitor::Capacitor, dc_current_source::DCCurrentSource,
@@ -12,356 +11,1012 @@ use spice_rs::{
 
 use crate::{
     utils::{
-        double_range::{DoubleRange, DoubleRangeInclusive},
         ipos2::{IPos2, Pos2Ext},
+        union_find::UnionFind,
         vec2ext::Vec2Ext,
     },
-    GRID_SIZE,
+    CAPACITOR_SOURCE, DC_CURRENT_SOURCE, DC_VOLTAGE_SOURCE, GRID_SIZE, GROUND_SOURCE,
+    INDUCTOR_SOURCE, RESISTOR_SOURCE,
 };
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct GuiCircuit {
-    /// List of wire points and its neighbors
-    pub nodes: HashMap<IPos2, Vec<IPos2>>,
-    /// List of starting positions of a node
-    pub nodes_starts: Vec<IPos2>,
-    /// Groups of wires into nodes
-    pub node_groups: Vec<HashSet<IPos2>>,
-    /// Wire points to be rendered
-    pub rendered_wires: Vec<Vec<Pos2>>,
+    /// Straight wire segments, only changed through the wire methods so the groups stay valid
+    wires: Vec<WireSegment>,
+    /// Groups of connected wires into nodes, as indexes into the wires
+    pub node_groups: Vec<Vec<usize>>,
+    /// Node group index of every wire
+    wire_groups: Vec<usize>,
+    /// Connectivity of the wires, extended as wires are added and regrouped when one is removed
+    wire_sets: UnionFind,
+    /// Wires passing through each grid cell, keyed on the cell's corner grid point, so lookups
+    /// only test the wires close by
+    wire_cells: HashMap<IPos2, Vec<usize>>,
+    /// Explicit junction dots, crossing wires only connect where one is placed
+    junctions: Vec<IPos2>,
     /// Circuit elements that are currently placed
     pub gui_elements: HashMap<u32, GuiElement>,
+    /// Names attached to wires, node groups sharing a name are one node
+    pub net_labels: Vec<NetLabel>,
     /// Element ids that were used but are currently free to allocate
     free_ids: Vec<u32>,
 }
 
 impl GuiCircuit {
     pub fn construct_circuit(&self) -> Option<Circuit> {
-        let mut circuit = Circuit::default();
+        self.netlist().map(|x| x.circuit())
+    }
 
-        let Some((_, ground)) = self
-            .gui_elements
-            .iter()
-            .find(|x| x.1.element == ElementType::Ground)
-        else {
-            return None;
-        };
-        let ground_node = self
-            .node_groups
-            .iter()
-            .enumerate()
-            .find(|x| x.1.contains(&ground.nodes[0]))
-            .unwrap()
-            .0;
-        println!("Ground {}", ground_node);
+    /// Resolves every element's pins to node numbers, with every grounded node group as node 0.
+    /// Elements are ordered by id so the numbering of branch currents is stable.
+    pub fn netlist(&self) -> Option<Netlist> {
+        let (node_numbers, node_count) = self.node_numbers()?;
 
-        let nodes = self
-            .node_groups
-            .iter()
-            .enumerate()
-            .map(|x| NodeId(x.0))
-            .collect::<Vec<NodeId>>();
-        println!("{:?}", self.node_groups);
-        println!("Nodes {} {:?}", nodes.len(), nodes);
-        circuit.nodes = nodes;
-
-        let mut bc_amount = 0;
-        for (_, gui_element) in self.gui_elements.iter() {
+        let mut gui_elements = self.gui_elements.values().collect::<Vec<&GuiElement>>();
+        gui_elements.sort_by_key(|x| x.id);
+
+        let mut elements = vec![];
+        for gui_element in gui_elements {
             if gui_element.element == ElementType::Ground {
                 continue;
             }
 
-            let Some(node_group1) = self
-                .node_groups
-                .iter()
-                .enumerate()
-                .find(|x| x.1.contains(&gui_element.nodes[0]))
-            else {
+            let Some(node_group1) = self.node_group_index(gui_element.nodes[0]) else {
                 continue;
             };
-            let node1 = Self::transcribe_node(node_group1.0, ground_node);
-
-            let Some(node_group2) = self
-                .node_groups
-                .iter()
-                .enumerate()
-                .find(|x| x.1.contains(&gui_element.nodes[1]))
-            else {
+            let Some(node_group2) = self.node_group_index(gui_element.nodes[1]) else {
                 continue;
             };
-            let node2 = Self::transcribe_node(node_group2.0, ground_node);
 
-            match gui_element.element {
-                ElementType::Resistor(resistance) => {
-                    circuit.add_element(Box::new(Resistor::new(resistance, node1, node2)));
-                    println!("Add resistor ({}, {})", node1.0, node2.0);
-                }
-                ElementType::DCVoltageSource(voltage) => {
-                    circuit.add_element(Box::new(DCVoltageSource::new(
-                        voltage, node1, node2, bc_amount,
-                    )));
-                    println!("Add DC Voltage Source ({}, {})", node1.0, node2.0);
+            elements.push(NetlistElement {
+                id: gui_element.id,
+                name: gui_element.name.clone(),
+                element: gui_element.element,
+                node1: NodeId(node_numbers[node_group1]),
+                node2: NodeId(node_numbers[node_group2]),
+            });
+        }
 
-                    bc_amount += 1;
-                }
-                ElementType::DCCurrentSource(amps) => {
-                    circuit.add_element(Box::new(DCCurrentSource::new(amps, node1, node2)));
-                    println!("Add DC Current Source ({}, {})", node1.0, node2.0);
-                }
-                ElementType::Capacitor(capacitance) => {
-                    circuit.add_element(Box::new(Capacitor::new(capacitance, node1, node2)));
-                    println!("Add Capacitor ({}, {})", node1.0, node2.0);
-                }
-                ElementType::Inductor(capacitance) => {
-                    circuit.add_element(Box::new(Inductor::new(
-                        capacitance,
-                        node1,
-                        node2,
-                        bc_amount,
-                    )));
-                    println!("Add Inductor ({}, {})", node1.0, node2.0);
+        Some(Netlist {
+            node_labels: self.node_labels(&node_numbers, node_count),
+            node_count,
+            elements,
+        })
+    }
 
-                    bc_amount += 1;
-                }
-                ElementType::Ground => (),
+    /// Maps every node group to a node number. Groups touching a ground or a ground label become
+    /// node 0, groups sharing a net label get the same number and the rest are numbered in group
+    /// order. Labels are compared case insensitively like SPICE node names. Also returns the
+    /// amount of nodes.
+    pub fn node_numbers(&self) -> Option<(Vec<usize>, usize)> {
+        let ground = self.node_groups.len();
+        let mut sets = UnionFind::new(ground + 1);
+        let mut grounded = false;
+        for gui_element in self.gui_elements.values() {
+            if gui_element.element != ElementType::Ground {
+                continue;
             }
+            if let Some(group_index) = self.node_group_index(gui_element.nodes[0]) {
+                sets.union(group_index, ground);
+                grounded = true;
+            }
+        }
+
+        let mut label_groups: HashMap<String, usize> = HashMap::new();
+        for net_label in self.net_labels.iter() {
+            let Some(group_index) = self.node_group_index(net_label.position) else {
+                continue;
+            };
+
+            if is_ground_label(&net_label.name) {
+                sets.union(group_index, ground);
+                grounded = true;
+            } else {
+                let first_group = *label_groups
+                    .entry(net_label.name.to_ascii_lowercase())
+                    .or_insert(group_index);
+                sets.union(group_index, first_group);
+            }
+        }
+        if !grounded {
+            return None;
         }
 
-        Some(circuit)
+        let ground_root = sets.find(ground);
+        let mut root_numbers: HashMap<usize, usize> = HashMap::new();
+        let node_numbers = (0..self.node_groups.len())
+            .map(|group_index| {
+                let root = sets.find(group_index);
+                if root == ground_root {
+                    return 0;
+                }
+
+                let next_number = root_numbers.len() + 1;
+                *root_numbers.entry(root).or_insert(next_number)
+            })
+            .collect();
+
+        Some((node_numbers, root_numbers.len() + 1))
     }
 
-    fn transcribe_node(node: usize, ground_node: usize) -> NodeId {
-        if ground_node != 0 {
-            if node == 0 {
-                return NodeId(ground_node);
-            } else if node == ground_node {
-                return NodeId(0);
+    /// Net label name of every node number, ground and unlabeled nodes have none
+    fn node_labels(&self, node_numbers: &[usize], node_count: usize) -> Vec<Option<String>> {
+        let mut node_labels = vec![None; node_count];
+        for net_label in self.net_labels.iter() {
+            let Some(group_index) = self.node_group_index(net_label.position) else {
+                continue;
+            };
+
+            let node = node_numbers[group_index];
+            if node != 0 && node_labels[node].is_none() {
+                node_labels[node] = Some(net_label.name.clone());
             }
         }
 
-        NodeId(node)
+        node_labels
+    }
+
+    /// Lowest free net label name such as `N2`
+    pub fn next_net_label_name(&self) -> String {
+        (1..)
+            .map(|number| format!("N{}", number))
+            .find(|name| {
+                self.net_labels
+                    .iter()
+                    .all(|x| !x.name.eq_ignore_ascii_case(name))
+            })
+            .unwrap()
+    }
+
+    /// Node group of the first wire running through `position`
+    pub fn node_group_index(&self, position: IPos2) -> Option<usize> {
+        self.wires_at(position).next().map(|x| self.wire_groups[x])
     }
 
-    pub fn add_element(&mut self, mut element: GuiElement) {
+    /// Indexes of the wires running through `position`, lowest first
+    fn wires_at(&self, position: IPos2) -> impl Iterator<Item = usize> + '_ {
+        self.wire_cells
+            .get(&grid_cell(position))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(move |x| self.wires[*x].contains(position))
+    }
+
+    /// Indexes of the wires sharing a grid cell with `segment`, which includes every wire it can
+    /// connect to
+    fn nearby_wires(&self, segment: &WireSegment) -> Vec<usize> {
+        let mut wires = segment
+            .cells()
+            .filter_map(|x| self.wire_cells.get(&x))
+            .flatten()
+            .copied()
+            .collect::<Vec<usize>>();
+        wires.sort_unstable();
+        wires.dedup();
+        wires
+    }
+
+    /// Wire segments of a node group
+    pub fn group_wires(&self, group: usize) -> impl Iterator<Item = &WireSegment> {
+        self.node_groups
+            .get(group)
+            .into_iter()
+            .flatten()
+            .map(|x| &self.wires[*x])
+    }
+
+    /// Grid points along the wires, where new wires can be started from
+    pub fn wire_grid_points(&self) -> HashSet<IPos2> {
+        self.wires.iter().flat_map(|x| x.grid_points()).collect()
+    }
+
+    /// Adds an element under a free id and returns that id, taken designators are replaced
+    pub fn add_element(&mut self, mut element: GuiElement) -> u32 {
         let id = self
             .free_ids
             .pop()
             .unwrap_or(self.gui_elements.len() as u32);
         element.id = id;
+        if self.validate_designator(id, &element).is_err() {
+            element.name = self.next_designator(&element.element);
+        }
+
+        self.gui_elements.insert(id, element);
+        id
+    }
+
+    /// Lowest free reference designator for the element's prefix, such as `R3`. Ground has none.
+    pub fn next_designator(&self, element: &ElementType) -> String {
+        let Some(prefix) = element.spice_prefix() else {
+            return String::new();
+        };
+
+        (1..)
+            .map(|number| format!("{}{}", prefix, number))
+            .find(|name| self.designator_owner(name).is_none())
+            .unwrap()
+    }
+
+    /// Checks that `element` can use its name as designator while having the id `id`
+    pub fn validate_designator(&self, id: u32, element: &GuiElement) -> Result<(), String> {
+        let Some(prefix) = element.element.spice_prefix() else {
+            return match element.name.is_empty() {
+                true => Ok(()),
+                false => Err("Ground has no name".to_string()),
+            };
+        };
+
+        let name = &element.name;
+        if name.len() < 2 || name.chars().any(|x| x.is_whitespace()) {
+            return Err(
+                "Name needs at least one character after the prefix and no spaces".to_string(),
+            );
+        }
+        if !name.starts_with(prefix) {
+            return Err(format!("Name must start with {}", prefix));
+        }
+        if self.designator_owner(name).is_some_and(|x| x != id) {
+            return Err(format!("{} is already used", name));
+        }
+
+        Ok(())
+    }
 
-        self.gui_elements.insert(element.id, element);
+    /// Id of the element named `name`, compared case insensitively like SPICE does
+    fn designator_owner(&self, name: &str) -> Option<u32> {
+        self.gui_elements
+            .values()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .map(|x| x.id)
     }
 
     pub fn remove_element(&mut self, id: u32) {
-        if let Some(element) = self.gui_elements.remove(&id) {
+        if self.gui_elements.remove(&id).is_some() {
             self.free_ids.push(id);
+        }
+    }
 
-            for node_position in element.nodes.into_iter() {
-                let indexes = self
-                    .nodes_starts
-                    .iter()
-                    .enumerate()
-                    .filter(|x| *x.1 == node_position)
-                    .map(|x| x.0)
-                    .collect::<Vec<usize>>();
-                for index in indexes {
-                    self.nodes_starts.remove(index);
-                }
+    /// Moves an element so it is centered on `center`. Wires attached to a pin are stretched
+    /// from the old pin position to the new one with an orthogonal wire that avoids the other
+    /// pins. Returns `false` if a pin could not be kept on its own net.
+    pub fn move_element(&mut self, id: u32, center: Pos2) -> bool {
+        // Nets before the move, the stretched wires must not join them through a foreign pin or
+        // a wire end on their path
+        let wire_groups = self.wire_groups.clone();
+        let unconnected_pins = self
+            .gui_elements
+            .values()
+            .filter(|x| x.id != id)
+            .flat_map(|x| x.nodes.iter().copied())
+            .filter(|x| self.node_group_index(*x).is_none())
+            .collect::<Vec<IPos2>>();
+
+        let Some(gui_element) = self.gui_elements.get_mut(&id) else {
+            return true;
+        };
+
+        let old_pins = gui_element.nodes.clone();
+        gui_element.rect = Rect::from_center_size(center, gui_element.rect.size());
+        gui_element.nodes = gui_element.pins_at(center);
+        let new_pins = gui_element.nodes.clone();
+
+        let old_groups = old_pins
+            .iter()
+            .map(|x| self.node_group_index(*x))
+            .collect::<Vec<Option<usize>>>();
+        let mut wires = vec![];
+        let mut connected = true;
+        for (index, (old_pin, new_pin)) in old_pins.iter().zip(new_pins.iter()).enumerate() {
+            let Some(old_group) = old_groups[index] else {
+                continue;
+            };
+            if self
+                .node_group_index(*new_pin)
+                .is_some_and(|x| x != old_group)
+            {
+                connected = false;
+                continue;
+            }
+            if old_pin == new_pin {
+                continue;
+            }
+
+            // Points the stretched wire must not touch, the other pins before and after the move
+            let forbidden = old_pins
+                .iter()
+                .zip(old_groups.iter())
+                .filter(|(_, group)| **group != Some(old_group))
+                .map(|(pin, _)| *pin)
+                .chain(
+                    new_pins
+                        .iter()
+                        .enumerate()
+                        .filter(|(other, _)| *other != index)
+                        .map(|(_, pin)| *pin),
+                )
+                .collect::<Vec<IPos2>>();
+            let x_first = [true, false].into_iter().find(|x_first| {
+                let middle = match x_first {
+                    true => IPos2::new(new_pin.x, old_pin.y),
+                    false => IPos2::new(old_pin.x, new_pin.y),
+                };
+                forbidden.iter().all(|pin| {
+                    !on_segment(*pin, *old_pin, middle) && !on_segment(*pin, middle, *new_pin)
+                })
+            });
+
+            match x_first {
+                Some(x_first) => wires.push((*old_pin, *new_pin, x_first)),
+                None => connected = false,
             }
         }
+
+        for (start, end, x_first) in wires {
+            self.add_orthogonal_wires(start, end, x_first);
+        }
+
+        connected && !self.joins_nets(&wire_groups, &unconnected_pins)
     }
 
-    pub fn remove_node(&mut self, position: IPos2) {
-        let Some((group_index, _)) = self
-            .node_groups
+    /// Whether wires added since `wire_groups` was taken merged two of its node groups or
+    /// connected one of the `unconnected` pins
+    fn joins_nets(&self, wire_groups: &[usize], unconnected: &[IPos2]) -> bool {
+        let mut old_groups: HashMap<usize, usize> = HashMap::new();
+        let merged = wire_groups
             .iter()
-            .enumerate()
-            .find(|x| x.1.contains(&position))
-        else {
-            println!("Return");
+            .zip(self.wire_groups.iter())
+            .any(|(old, new)| *old_groups.entry(*new).or_insert(*old) != *old);
+
+        merged
+            || unconnected
+                .iter()
+                .any(|x| self.node_group_index(*x).is_some())
+    }
+
+    /// Moves several elements by `offset`, stretching their wires like [`Self::move_element`].
+    /// Returns `false` if any pin could not be kept on its own net.
+    pub fn move_elements(&mut self, ids: &[u32], offset: Vec2) -> bool {
+        let mut centers = ids
+            .iter()
+            .filter_map(|x| self.gui_elements.get(x))
+            .map(|x| (x.id, x.rect.center()))
+            .collect::<Vec<(u32, Pos2)>>();
+        // Elements furthest along the offset move first so they do not land on the old pins of
+        // the ones behind them
+        centers.sort_by(|a, b| {
+            let distance = |center: Pos2| center.to_vec2().dot(offset);
+            distance(b.1).total_cmp(&distance(a.1))
+        });
+
+        centers.into_iter().fold(true, |connected, (id, center)| {
+            self.move_element(id, center + offset) && connected
+        })
+    }
+
+    /// Removes every wire of the node group at `position` and the net labels on it
+    pub fn remove_node(&mut self, position: IPos2) {
+        let Some(group_index) = self.node_group_index(position) else {
             return;
         };
         println!("Remove Group {}", group_index);
-        let group = self.node_groups.swap_remove(group_index);
 
-        group.iter().for_each(|position| {
-            self.nodes.remove(position);
+        let wire_groups = std::mem::take(&mut self.wire_groups);
+        let mut old_groups = vec![];
+        let mut group_wires = vec![];
+        let mut index = 0;
+        self.wires.retain(|wire| {
+            let keep = wire_groups[index] != group_index;
+            match keep {
+                true => old_groups.push(wire_groups[index]),
+                false => group_wires.push(*wire),
+            }
+            index += 1;
+            keep
         });
-        if let Some(starting_point) = self.nodes_starts.iter().find(|x| group.contains(*x)) {
-            self.rendered_wires
-                .retain(|x| !x.contains(&starting_point.to_pos2()));
-        }
-        self.nodes_starts.retain(|x| !group.contains(x));
+        self.net_labels
+            .retain(|x| !group_wires.iter().any(|wire| wire.contains(x.position)));
+        // Removing a whole group leaves the others connected as they were
+        self.regroup_wires(&old_groups, &[]);
     }
 
-    pub fn add_orthogonal_wires(&mut self, start: IPos2, end: IPos2, x_first: bool) {
-        if !self.nodes_starts.contains(&end) {
-            self.nodes_starts.push(end);
+    /// Rect around every element, wire and net label, `None` for an empty schematic
+    pub fn bounding_rect(&self) -> Option<Rect> {
+        let points = |x: IPos2| Rect::from_min_max(x.to_pos2(), x.to_pos2());
+
+        self.gui_elements
+            .values()
+            .map(|x| x.rect)
+            .chain(self.wires.iter().map(|x| x.rect()))
+            .chain(self.net_labels.iter().map(|x| points(x.position)))
+            .reduce(|a, b| a.union(b))
+    }
+
+    pub fn wire_segments(&self) -> &[WireSegment] {
+        &self.wires
+    }
+
+    pub fn junctions(&self) -> &[IPos2] {
+        &self.junctions
+    }
+
+    /// Places a junction dot connecting every wire running through `position`. Returns `false`
+    /// if there is no wire or already a junction.
+    pub fn add_junction(&mut self, position: IPos2) -> bool {
+        if self.junctions.contains(&position) || self.node_group_index(position).is_none() {
+            return false;
+        }
+
+        self.junctions.push(position);
+        let wires = self.wires_at(position).collect::<Vec<usize>>();
+        for wire in wires.iter().skip(1) {
+            self.wire_sets.union(wires[0], *wire);
         }
-        if !self.nodes_starts.contains(&start) {
-            self.nodes_starts.push(start);
+        self.update_node_groups();
+        true
+    }
+
+    /// Removes a junction dot, which can split its node group. Returns `false` if there is none.
+    pub fn remove_junction(&mut self, position: IPos2) -> bool {
+        let Some(index) = self.junctions.iter().position(|x| *x == position) else {
+            return false;
+        };
+
+        let old_groups = self.wire_groups.clone();
+        let split = self
+            .node_group_index(position)
+            .into_iter()
+            .collect::<Vec<usize>>();
+        self.junctions.remove(index);
+        self.regroup_wires(&old_groups, &split);
+        true
+    }
+
+    /// Points drawn as junction dots, the explicit junctions and every point where three or more
+    /// wire ends meet, counting a wire passing through as two ends
+    pub fn junction_dots(&self) -> Vec<IPos2> {
+        let mut dots = self.junctions.clone();
+        for position in self.wires.iter().flat_map(|x| [x.start, x.end]) {
+            if dots.contains(&position) {
+                continue;
+            }
+
+            let ends = self
+                .wires_at(position)
+                .map(|x| &self.wires[x])
+                .map(|wire| match position {
+                    x if x == wire.start || x == wire.end => 1,
+                    _ => 2,
+                })
+                .sum::<usize>();
+            if ends >= 3 {
+                dots.push(position);
+            }
         }
 
+        dots
+    }
+
+    /// Whether two wires are connected, by an end of one lying on the other or by a junction
+    fn wires_connect(&self, a: &WireSegment, b: &WireSegment) -> bool {
+        a.touches(b) || a.crossing(b).is_some_and(|x| self.junctions.contains(&x))
+    }
+
+    /// Wire segment closest to `position` that is at most `tolerance` away
+    pub fn wire_segment_at(&self, position: Pos2, tolerance: f32) -> Option<WireSegment> {
+        self.wires
+            .iter()
+            .map(|x| (*x, x.distance(position)))
+            .filter(|x| x.1 <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|x| x.0)
+    }
+
+    /// Removes one straight wire piece, splitting its node group if that disconnects it. Net
+    /// labels left without a wire are removed. Returns `false` if there is no such segment.
+    pub fn remove_wire_segment(&mut self, segment: WireSegment) -> bool {
+        let Some(index) = self
+            .wires_at(segment.start)
+            .find(|x| self.wires[*x] == segment)
+        else {
+            return false;
+        };
+
+        // Only the group the segment was in can split
+        let mut old_groups = self.wire_groups.clone();
+        let group = old_groups.remove(index);
+        self.wires.remove(index);
+        self.regroup_wires(&old_groups, &[group]);
+
+        let net_labels = std::mem::take(&mut self.net_labels);
+        self.net_labels = net_labels
+            .into_iter()
+            .filter(|x| self.wires_at(x.position).next().is_some())
+            .collect();
+        true
+    }
+
+    /// Adds an orthogonal wire from `start` to `end` as up to two straight segments
+    pub fn add_orthogonal_wires(&mut self, start: IPos2, end: IPos2, x_first: bool) {
         let middle_position = match x_first {
             true => IPos2::new(end.x, start.y),
             false => IPos2::new(start.x, end.y),
         };
-        let mut previous_position = None;
 
-        self.rendered_wires.push(vec![
-            start.to_pos2(),
-            middle_position.to_pos2(),
-            end.to_pos2(),
-        ]);
+        for (start, end) in [(start, middle_position), (middle_position, end)] {
+            if start != end {
+                self.add_wire(WireSegment::new(start, end));
+            }
+        }
+    }
 
-        if x_first {
-            self.add_wire_axis(
-                &mut previous_position,
-                middle_position,
-                DoubleRange::new(start.x, middle_position.x, 1),
-                true,
-            );
-            self.add_wire_axis(
-                &mut previous_position,
-                middle_position,
-                DoubleRangeInclusive::new(middle_position.y, end.y, 1),
-                false,
-            );
-        } else {
-            self.add_wire_axis(
-                &mut previous_position,
-                middle_position,
-                DoubleRange::new(start.y, middle_position.y, 1),
-                false,
-            );
-            self.add_wire_axis(
-                &mut previous_position,
-                middle_position,
-                DoubleRangeInclusive::new(middle_position.x, end.x, 1),
-                true,
-            );
+    /// Adds a straight wire and joins it with the wires it touches
+    pub fn add_wire(&mut self, segment: WireSegment) {
+        let index = self.wire_sets.push();
+        for other in self.nearby_wires(&segment) {
+            if self.wires_connect(&self.wires[other], &segment) {
+                self.wire_sets.union(other, index);
+            }
         }
 
-        self.group_wires_into_nodes();
+        for cell in segment.cells() {
+            self.wire_cells.entry(cell).or_default().push(index);
+        }
+        self.wires.push(segment);
+        self.update_node_groups();
     }
 
-    fn add_wire_axis(
-        &mut self,
-        previous_position: &mut Option<IPos2>,
-        middle_position: IPos2,
-        range: impl Iterator<Item = i32>,
-        x_axis: bool,
-    ) {
-        for i in range {
-            let position = match x_axis {
-                true => IPos2::new(i, middle_position.y),
-                false => IPos2::new(middle_position.x, i),
-            };
+    /// Replaces all wires and junctions, used when loading a schematic
+    pub fn set_wires(&mut self, wires: Vec<WireSegment>, junctions: Vec<IPos2>) {
+        let old_groups = vec![0; wires.len()];
+        self.wires = wires;
+        self.junctions = junctions;
+        self.regroup_wires(&old_groups, &[0]);
+    }
 
-            if let Some(previous_position) = previous_position {
-                if let Some(neighbors) = self.nodes.get_mut(previous_position) {
-                    if !neighbors.contains(&position) {
-                        neighbors.push(position);
-                    }
-                }
+    /// Recomputes the connectivity after wires or a junction were removed, union find can not
+    /// split sets. `old_groups` holds the node group every remaining wire was in before, only the
+    /// wires of the `split` groups are joined again with their neighbors while every other group
+    /// is kept as it was. Junctions no longer on a wire are dropped.
+    fn regroup_wires(&mut self, old_groups: &[usize], split: &[usize]) {
+        // Removed wires shift the indexes after them
+        self.wire_cells.clear();
+        for (index, wire) in self.wires.iter().enumerate() {
+            for cell in wire.cells() {
+                self.wire_cells.entry(cell).or_default().push(index);
+            }
+        }
+        let junctions = std::mem::take(&mut self.junctions);
+        self.junctions = junctions
+            .into_iter()
+            .filter(|x| self.wires_at(*x).next().is_some())
+            .collect();
+
+        self.wire_sets = UnionFind::new(self.wires.len());
+        let mut first_wires: HashMap<usize, usize> = HashMap::new();
+        for (index, group) in old_groups.iter().enumerate() {
+            if !split.contains(group) {
+                let first_wire = *first_wires.entry(*group).or_insert(index);
+                self.wire_sets.union(first_wire, index);
+                continue;
             }
 
-            if let Some(neighbors) = self.nodes.get_mut(&position) {
-                if let Some(previous_position) = previous_position {
-                    if !neighbors.contains(&previous_position) {
-                        neighbors.push(*previous_position);
-                    }
+            for other in self.nearby_wires(&self.wires[index]) {
+                if other < index && self.wires_connect(&self.wires[other], &self.wires[index]) {
+                    self.wire_sets.union(other, index);
                 }
-            } else {
-                self.nodes.insert(
-                    position,
-                    previous_position.iter().map(|x| *x).collect::<Vec<IPos2>>(),
-                );
+            }
+        }
+
+        self.update_node_groups();
+    }
+
+    /// Numbers the wire sets in order of their first wire
+    fn update_node_groups(&mut self) {
+        let mut root_groups: HashMap<usize, usize> = HashMap::new();
+        self.node_groups.clear();
+        self.wire_groups.clear();
+
+        for index in 0..self.wires.len() {
+            let root = self.wire_sets.find(index);
+            let next_group = root_groups.len();
+            let group = *root_groups.entry(root).or_insert(next_group);
+            if group == self.node_groups.len() {
+                self.node_groups.push(vec![]);
             }
 
-            *previous_position = Some(position);
+            self.node_groups[group].push(index);
+            self.wire_groups.push(group);
         }
     }
+}
 
-    fn group_wires_into_nodes(&mut self) {
-        let mut wires = self.nodes.clone();
-        let mut wire_starts = self.nodes_starts.clone();
-        let mut visited: HashSet<IPos2> = HashSet::new();
-        let mut queue: VecDeque<IPos2> = VecDeque::new();
-        let mut group: HashSet<IPos2> = HashSet::new();
-        let mut groups: Vec<HashSet<IPos2>> = vec![];
+/// Straight wire piece between two points, the same segment regardless of its direction
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WireSegment {
+    pub start: IPos2,
+    pub end: IPos2,
+}
 
-        let Some(start) = wire_starts.pop() else {
-            return;
-        };
-        wires.remove(&start);
-        wire_starts.swap_remove(0);
-        visited.insert(start);
-        queue.push_back(start);
-        group.insert(start);
+impl WireSegment {
+    /// Segment between two points, ordered so both directions compare equal
+    pub fn new(start: IPos2, end: IPos2) -> Self {
+        match (start.x, start.y) <= (end.x, end.y) {
+            true => Self { start, end },
+            false => Self {
+                start: end,
+                end: start,
+            },
+        }
+    }
 
-        while !wires.is_empty() {
-            let position = queue.pop_front().unwrap();
-            let neighbors = self.nodes.get(&position).unwrap();
+    pub fn rect(&self) -> Rect {
+        Rect::from_two_pos(self.start.to_pos2(), self.end.to_pos2())
+    }
 
-            visited.insert(position);
+    pub fn contains(&self, position: IPos2) -> bool {
+        on_segment(position, self.start, self.end)
+    }
 
-            for neighbor in neighbors.iter() {
-                if visited.contains(neighbor) {
-                    continue;
-                }
+    /// Whether an end of either wire lies on the other, wires that only cross do not touch
+    pub fn touches(&self, other: &WireSegment) -> bool {
+        self.contains(other.start)
+            || self.contains(other.end)
+            || other.contains(self.start)
+            || other.contains(self.end)
+    }
 
-                group.insert(*neighbor);
-                queue.push_back(*neighbor);
-                wires.remove(neighbor);
-                if wire_starts.contains(neighbor) {
-                    let index = wire_starts
-                        .iter()
-                        .enumerate()
-                        .find(|x| x.1 == neighbor)
-                        .map(|x| x.0)
-                        .unwrap();
-                    wire_starts.remove(index);
+    /// Point where the two wires cross without either ending there
+    pub fn crossing(&self, other: &WireSegment) -> Option<IPos2> {
+        let (horizontal, vertical) =
+            match (self.start.y == self.end.y, other.start.y == other.end.y) {
+                (true, false) => (self, other),
+                (false, true) => (other, self),
+                _ => return None,
+            };
+        let position = IPos2::new(vertical.start.x, horizontal.start.y);
+        let is_end = [self.start, self.end, other.start, other.end].contains(&position);
+
+        (self.contains(position) && other.contains(position) && !is_end).then_some(position)
+    }
+
+    /// Corner grid points of the grid cells the segment passes through, see [`grid_cell`]
+    fn cells(&self) -> impl Iterator<Item = IPos2> {
+        let (first, last) = (grid_cell(self.start), grid_cell(self.end));
+
+        (first.x.min(last.x)..=first.x.max(last.x))
+            .step_by(GRID_SIZE as usize)
+            .flat_map(move |x| {
+                (first.y.min(last.y)..=first.y.max(last.y))
+                    .step_by(GRID_SIZE as usize)
+                    .map(move |y| IPos2::new(x, y))
+            })
+    }
+
+    /// Points on the segment that lie on the grid
+    pub fn grid_points(&self) -> impl Iterator<Item = IPos2> + '_ {
+        let first = |x: i32| x.div_euclid(GRID_SIZE) + (x.rem_euclid(GRID_SIZE) != 0) as i32;
+        let (min, max) = (self.start, self.end);
+
+        (first(min.x)..=max.x.div_euclid(GRID_SIZE)).flat_map(move |x| {
+            (first(min.y)..=max.y.div_euclid(GRID_SIZE))
+                .map(move |y| IPos2::new(x * GRID_SIZE, y * GRID_SIZE))
+        })
+    }
+
+    /// Distance from `position` to the closest point of the segment
+    pub fn distance(&self, position: Pos2) -> f32 {
+        position.distance(position.clamp(self.start.to_pos2(), self.end.to_pos2()))
+    }
+}
+
+/// Grid point at the top left corner of the grid cell holding `position`
+fn grid_cell(position: IPos2) -> IPos2 {
+    IPos2::new(
+        position.x.div_euclid(GRID_SIZE) * GRID_SIZE,
+        position.y.div_euclid(GRID_SIZE) * GRID_SIZE,
+    )
+}
+
+/// Whether `position` lies on the axis aligned segment from `start` to `end`
+fn on_segment(position: IPos2, start: IPos2, end: IPos2) -> bool {
+    position.x >= start.x.min(end.x)
+        && position.x <= start.x.max(end.x)
+        && position.y >= start.y.min(end.y)
+        && position.y <= start.y.max(end.y)
+}
+
+/// Circuit elements with their pins resolved to node numbers
+#[derive(Clone)]
+pub struct Netlist {
+    /// Amount of nodes including ground
+    pub node_count: usize,
+    /// Net label of every node number
+    pub node_labels: Vec<Option<String>>,
+    pub elements: Vec<NetlistElement>,
+}
+
+impl Netlist {
+    /// Net label or number of a node, as written in exported netlists
+    pub fn node_name(&self, node: usize) -> String {
+        match &self.node_labels[node] {
+            Some(label) => label.clone(),
+            None => node.to_string(),
+        }
+    }
+
+    /// Stamp of every element in netlist order, `None` for ground. Voltage sources and inductors
+    /// number their branch currents in netlist order.
+    pub fn stamps(&self) -> Vec<Option<Stamp>> {
+        let mut branches = 0;
+        let mut next_branch = || {
+            branches += 1;
+            branches - 1
+        };
+
+        self.elements
+            .iter()
+            .map(|x| match x.element {
+                ElementType::Resistor(resistance) => Some(Stamp::Resistor(resistance as f64)),
+                ElementType::DCVoltageSource(voltage) => {
+                    Some(Stamp::VoltageSource(voltage as f64, next_branch()))
                 }
-            }
+                ElementType::DCCurrentSource(amps) => Some(Stamp::CurrentSource(amps as f64)),
+                ElementType::Capacitor(capacitance) => Some(Stamp::Capacitor(capacitance as f64)),
+                ElementType::Inductor(inductance) => {
+                    Some(Stamp::Inductor(inductance as f64, next_branch()))
+                }
+                ElementType::Ground => None,
+            })
+            .collect()
+    }
 
-            if queue.is_empty() || wires.is_empty() {
-                groups.push(std::mem::take(&mut group));
-                if let Some(wire_start) = wire_starts.pop() {
-                    queue.push_back(wire_start);
-                    wires.remove(&wire_start);
-                    group.insert(wire_start);
+    /// Amount of branch currents, see [`Self::stamps`]
+    pub fn branch_count(&self) -> usize {
+        self.stamps()
+            .iter()
+            .filter(|x| x.is_some_and(|x| x.branch().is_some()))
+            .count()
+    }
+
+    /// Builds the spice-rs circuit from the element stamps, node numbers and element order are
+    /// kept
+    pub fn circuit(&self) -> Circuit {
+        let mut circuit = Circuit::default();
+
+        circuit.nodes = (0..self.node_count).map(NodeId).collect();
+
+        for (netlist_element, stamp) in self.elements.iter().zip(self.stamps()) {
+            let node1 = netlist_element.node1;
+            let node2 = netlist_element.node2;
+
+            match stamp {
+                Some(Stamp::Resistor(resistance)) => {
+                    circuit.add_element(Box::new(Resistor::new(resistance as f32, node1, node2)));
+                }
+                Some(Stamp::VoltageSource(voltage, branch)) => {
+                    circuit.add_element(Box::new(DCVoltageSource::new(
+                        voltage as f32,
+                        node1,
+                        node2,
+                        branch,
+                    )));
+                }
+                Some(Stamp::CurrentSource(amps)) => {
+                    circuit.add_element(Box::new(DCCurrentSource::new(amps as f32, node1, node2)));
+                }
+                Some(Stamp::Capacitor(capacitance)) => {
+                    circuit.add_element(Box::new(Capacitor::new(capacitance as f32, node1, node2)));
                 }
+                Some(Stamp::Inductor(inductance, branch)) => {
+                    circuit.add_element(Box::new(Inductor::new(
+                        inductance as f32,
+                        node1,
+                        node2,
+                        branch,
+                    )));
+                }
+                None => (),
             }
         }
 
-        self.node_groups = groups;
+        circuit
+    }
+}
+
+/// Model of an element in the circuit equations. The spice-rs circuit and the MNA analyses are
+/// both built from [`Netlist::stamps`], so they share the element models and the numbering of
+/// the branch currents.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stamp {
+    Resistor(f64),
+    /// Current driven from the first node through the source into the second
+    CurrentSource(f64),
+    /// Voltage and the index of the branch current
+    VoltageSource(f64, usize),
+    Capacitor(f64),
+    /// Inductance and the index of the branch current
+    Inductor(f64, usize),
+}
+
+impl Stamp {
+    /// Index of the branch current through the element, voltage sources and inductors have one
+    pub fn branch(&self) -> Option<usize> {
+        match self {
+            Stamp::VoltageSource(_, branch) | Stamp::Inductor(_, branch) => Some(*branch),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct NetlistElement {
+    /// Id of the `GuiElement` this element was placed as
+    pub id: u32,
+    /// Reference designator of the element
+    pub name: String,
+    pub element: ElementType,
+    pub node1: NodeId,
+    pub node2: NodeId,
+}
+
+/// Name attached to the wire at `position`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetLabel {
+    pub position: IPos2,
+    pub name: String,
+}
+
+impl NetLabel {
+    pub fn validate_name(name: &str) -> Result<(), String> {
+        if name.is_empty()
+            || name
+                .chars()
+                .any(|x| x.is_whitespace() || x == '(' || x == ')')
+        {
+            return Err("Name can not be empty or contain spaces and parentheses".to_string());
+        }
+        // Plain numbers would clash with the numbers of unlabeled nodes in exported netlists
+        if name != "0" && name.chars().all(|x| x.is_ascii_digit()) {
+            return Err("Name can not be a number other than 0".to_string());
+        }
+
+        Ok(())
     }
 }
 
+/// Whether a net name refers to ground, like `0` and `GND` in SPICE netlists
+pub fn is_ground_label(name: &str) -> bool {
+    name == "0" || name.eq_ignore_ascii_case("gnd")
+}
+
+/// Shape of the random deviation from the nominal value
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Distribution {
+    #[default]
+    Uniform,
+    /// Normal distribution with the tolerance at three standard deviations, cut off there
+    Gaussian,
+}
+
+impl Distribution {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Distribution::Uniform => "Uniform",
+            Distribution::Gaussian => "Gaussian",
+        }
+    }
+}
+
+/// How far the real value of an element may be off its nominal value, used by Monte Carlo runs
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Tolerance {
+    pub percent: f32,
+    pub distribution: Distribution,
+}
+
 #[derive(Clone)]
 pub struct GuiElement {
     id: u32,
+    /// Reference designator such as `R1`, empty for ground
+    pub name: String,
     pub element: ElementType,
+    pub tolerance: Tolerance,
     pub rect: Rect,
     pub nodes: Vec<IPos2>,
     pub image: Image<'static>,
 }
 
 impl GuiElement {
+    /// Rect an element image takes up when centered on `center`
+    pub fn rect_at(image: &Image, center: Pos2) -> Rect {
+        Rect::from_center_size(
+            center,
+            image.calc_size(Vec2::new(128.0, 62.5), image.size()),
+        )
+    }
+
     pub fn new(element: ElementType, rect: Rect, image: Image<'static>) -> Self {
-        let rotation = image.image_options().rotation.unwrap_or_default().0.angle();
+        let rotation = image_rotation(&image);
+        let flip = image_flip(&image);
         Self {
             id: 0,
+            name: String::new(),
             element,
+            tolerance: Tolerance::default(),
             rect,
-            nodes: element.node_positions(rect.center(), rotation),
-            image: image.rotate(rotation, Vec2::splat(0.5)),
+            nodes: element.node_positions(rect.center(), rotation, flip),
+            image: oriented_image(image, rotation, flip),
         }
     }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Pin positions the element would have when centered on `center`
+    pub fn pins_at(&self, center: Pos2) -> Vec<IPos2> {
+        self.element
+            .node_positions(center, self.rotation(), self.flip())
+    }
+
+    pub fn rotation(&self) -> f32 {
+        image_rotation(&self.image)
+    }
+
+    /// Whether the element is mirrored along its own x and y axis, before rotating
+    pub fn flip(&self) -> (bool, bool) {
+        image_flip(&self.image)
+    }
+
+    /// Sets the image orientation and moves the pins to match it
+    pub fn set_orientation(&mut self, rotation: f32, flip: (bool, bool)) {
+        self.image = oriented_image(self.image.clone(), rotation, flip);
+        self.nodes = self.pins_at(self.rect.center());
+    }
+
+    /// Rotates the element by a quarter turn around its center
+    pub fn rotate_quarter(&mut self) {
+        self.set_orientation(self.rotation() + std::f32::consts::FRAC_PI_2, self.flip());
+    }
+
+    /// Mirrors the element left to right as seen on screen
+    pub fn flip_horizontal(&mut self) {
+        let (flip_x, flip_y) = self.flip();
+        self.set_orientation(-self.rotation(), (!flip_x, flip_y));
+    }
+
+    /// Mirrors the element top to bottom as seen on screen
+    pub fn flip_vertical(&mut self) {
+        let (flip_x, flip_y) = self.flip();
+        self.set_orientation(-self.rotation(), (flip_x, !flip_y));
+    }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+pub fn image_rotation(image: &Image) -> f32 {
+    image.image_options().rotation.unwrap_or_default().0.angle()
+}
+
+/// Mirroring of an image, stored as a flipped uv rect
+pub fn image_flip(image: &Image) -> (bool, bool) {
+    let uv = image.image_options().uv;
+    (uv.min.x > uv.max.x, uv.min.y > uv.max.y)
+}
+
+/// Mirrors `image` and then rotates it by `rotation`, snapped to a quarter turn
+pub fn oriented_image(image: Image<'static>, rotation: f32, flip: (bool, bool)) -> Image<'static> {
+    let quarter = std::f32::consts::FRAC_PI_2;
+    let rotation = ((rotation / quarter).round() * quarter).rem_euclid(std::f32::consts::TAU);
+    let (flip_x, flip_y) = (flip.0 as i32 as f32, flip.1 as i32 as f32);
+    let uv = Rect::from_min_max(
+        Pos2::new(flip_x, flip_y),
+        Pos2::new(1.0 - flip_x, 1.0 - flip_y),
+    );
+
+    image.uv(uv).rotate(rotation, Vec2::splat(0.5))
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ElementType {
     Ground,
     Resistor(f32),
@@ -372,15 +1027,32 @@ pub enum ElementType {
 }
 
 impl ElementType {
-    fn node_positions(&self, center: Pos2, rotation: f32) -> Vec<IPos2> {
+    fn node_positions(&self, center: Pos2, rotation: f32, flip: (bool, bool)) -> Vec<IPos2> {
+        let offsets = match self {
+            ElementType::Ground => vec![Vec2::new(0.0, -16.0)],
+            _ => vec![Vec2::new(-32.0, 0.0), Vec2::new(32.0, 0.0)],
+        };
+
+        offsets
+            .into_iter()
+            .map(|offset| {
+                let offset = Vec2::new(
+                    if flip.0 { -offset.x } else { offset.x },
+                    if flip.1 { -offset.y } else { offset.y },
+                );
+                (center + offset.rotate(rotation)).to_ipos2(GRID_SIZE)
+            })
+            .collect()
+    }
+
+    pub fn value(&self) -> Option<f32> {
         match self {
-            ElementType::Ground => {
-                vec![(center - Vec2::new(0.0, 16.0).rotate(rotation)).to_ipos2(GRID_SIZE)]
-            }
-            _ => vec![
-                (center - Vec2::new(32.0, 0.0).rotate(rotation)).to_ipos2(GRID_SIZE),
-                (center + Vec2::new(32.0, 0.0).rotate(rotation)).to_ipos2(GRID_SIZE),
-            ],
+            ElementType::Resistor(resistance) => Some(*resistance),
+            ElementType::DCVoltageSource(voltage) => Some(*voltage),
+            ElementType::DCCurrentSource(amps) => Some(*amps),
+            ElementType::Capacitor(capacitance) => Some(*capacitance),
+            ElementType::Inductor(inductance) => Some(*inductance),
+            ElementType::Ground => None,
         }
     }
 
@@ -395,6 +1067,29 @@ impl ElementType {
         }
     }
 
+    pub fn image_source(&self) -> ImageSource<'static> {
+        match self {
+            ElementType::Ground => GROUND_SOURCE,
+            ElementType::Resistor(_) => RESISTOR_SOURCE,
+            ElementType::DCVoltageSource(_) => DC_VOLTAGE_SOURCE,
+            ElementType::DCCurrentSource(_) => DC_CURRENT_SOURCE,
+            ElementType::Capacitor(_) => CAPACITOR_SOURCE,
+            ElementType::Inductor(_) => INDUCTOR_SOURCE,
+        }
+    }
+
+    /// Letter that starts the element's card in a SPICE netlist
+    pub fn spice_prefix(&self) -> Option<char> {
+        match self {
+            ElementType::Resistor(_) => Some('R'),
+            ElementType::DCVoltageSource(_) => Some('V'),
+            ElementType::DCCurrentSource(_) => Some('I'),
+            ElementType::Capacitor(_) => Some('C'),
+            ElementType::Inductor(_) => Some('L'),
+            ElementType::Ground => None,
+        }
+    }
+
     pub fn display_name(&self) -> &str {
         match self {
             ElementType::Ground => "Ground",
@@ -441,46 +1136,89 @@ impl ToPlaceElement {
     }
 }
 
+/// Places a 64 by 32 element centered on `center` for the tests and returns its id
+#[cfg(test)]
+pub(crate) fn add_test_element(
+    circuit: &mut GuiCircuit,
+    element: ElementType,
+    center: Pos2,
+    rotation: f32,
+) -> u32 {
+    let image = Image::new(element.image_source()).rotate(rotation, Vec2::splat(0.5));
+    let rect = Rect::from_center_size(center, Vec2::new(64.0, 32.0));
+    circuit.add_element(GuiElement::new(element, rect, image))
+}
+
 #[cfg(test)]
 mod tests {
-    use egui::Pos2;
-    use hashbrown::{HashMap, HashSet};
+    use egui::{Image, Pos2, Rect, Vec2};
+    use hashbrown::HashSet;
+
+    use crate::{
+        analysis::{element, numbered_netlist},
+        utils::ipos2::IPos2,
+    };
+
+    use super::{
+        add_test_element, ElementType, GuiCircuit, GuiElement, NetLabel, Stamp, WireSegment,
+    };
+
+    fn new_element(element: ElementType) -> GuiElement {
+        let image = Image::new(element.image_source());
+        let rect = Rect::from_center_size(Pos2::new(64.0, 64.0), Vec2::new(64.0, 32.0));
+        GuiElement::new(element, rect, image)
+    }
 
-    use crate::utils::ipos2::IPos2;
+    fn element_names(circuit: &GuiCircuit) -> Vec<String> {
+        let mut names = circuit
+            .gui_elements
+            .values()
+            .map(|x| x.name.clone())
+            .collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+    /// Every point covered by the wires of each node group
+    fn group_points(circuit: &GuiCircuit) -> Vec<HashSet<IPos2>> {
+        (0..circuit.node_groups.len())
+            .map(|group| {
+                circuit
+                    .group_wires(group)
+                    .flat_map(|wire| {
+                        (wire.start.x..=wire.end.x).flat_map(move |x| {
+                            (wire.start.y..=wire.end.y).map(move |y| IPos2::new(x, y))
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
 
-    use super::GuiCircuit;
+    fn segment(start: (i32, i32), end: (i32, i32)) -> WireSegment {
+        WireSegment::new(IPos2::new(start.0, start.1), IPos2::new(end.0, end.1))
+    }
 
     #[test]
     fn add_orthogonal_wires_x_first() {
         let mut circuit = GuiCircuit::default();
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(3, 3), true);
 
-        assert_eq!(circuit.nodes_starts, [IPos2::new(3, 3), IPos2::new(0, 0)]);
-
-        let mut test_nodes = HashMap::new();
-        test_nodes.insert(IPos2::new(0, 0), vec![IPos2::new(1, 0)]);
-        test_nodes.insert(IPos2::new(1, 0), vec![IPos2::new(0, 0), IPos2::new(2, 0)]);
-        test_nodes.insert(IPos2::new(2, 0), vec![IPos2::new(1, 0), IPos2::new(3, 0)]);
-        test_nodes.insert(IPos2::new(3, 0), vec![IPos2::new(2, 0), IPos2::new(3, 1)]);
-        test_nodes.insert(IPos2::new(3, 1), vec![IPos2::new(3, 0), IPos2::new(3, 2)]);
-        test_nodes.insert(IPos2::new(3, 2), vec![IPos2::new(3, 1), IPos2::new(3, 3)]);
-        test_nodes.insert(IPos2::new(3, 3), vec![IPos2::new(3, 2)]);
-
-        assert_eq!(circuit.nodes, test_nodes);
-
-        let test_groups = HashSet::from_iter(
-            vec![
-                IPos2::new(0, 0),
-                IPos2::new(1, 0),
-                IPos2::new(2, 0),
-                IPos2::new(3, 0),
-                IPos2::new(3, 1),
-                IPos2::new(3, 2),
-                IPos2::new(3, 3),
-            ]
-            .into_iter(),
+        assert_eq!(
+            circuit.wire_segments(),
+            [segment((0, 0), (3, 0)), segment((3, 0), (3, 3))]
         );
-        assert_eq!(circuit.node_groups, vec![test_groups]);
+
+        let test_groups = HashSet::from_iter(vec![
+            IPos2::new(0, 0),
+            IPos2::new(1, 0),
+            IPos2::new(2, 0),
+            IPos2::new(3, 0),
+            IPos2::new(3, 1),
+            IPos2::new(3, 2),
+            IPos2::new(3, 3),
+        ]);
+        assert_eq!(group_points(&circuit), vec![test_groups]);
     }
 
     #[test]
@@ -488,32 +1226,21 @@ mod tests {
         let mut circuit = GuiCircuit::default();
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(3, 3), false);
 
-        assert_eq!(circuit.nodes_starts, [IPos2::new(3, 3), IPos2::new(0, 0)]);
-
-        let mut test_nodes = HashMap::new();
-        test_nodes.insert(IPos2::new(0, 0), vec![IPos2::new(0, 1)]);
-        test_nodes.insert(IPos2::new(0, 1), vec![IPos2::new(0, 0), IPos2::new(0, 2)]);
-        test_nodes.insert(IPos2::new(0, 2), vec![IPos2::new(0, 1), IPos2::new(0, 3)]);
-        test_nodes.insert(IPos2::new(0, 3), vec![IPos2::new(0, 2), IPos2::new(1, 3)]);
-        test_nodes.insert(IPos2::new(1, 3), vec![IPos2::new(0, 3), IPos2::new(2, 3)]);
-        test_nodes.insert(IPos2::new(2, 3), vec![IPos2::new(1, 3), IPos2::new(3, 3)]);
-        test_nodes.insert(IPos2::new(3, 3), vec![IPos2::new(2, 3)]);
-
-        assert_eq!(circuit.nodes, test_nodes);
-
-        let test_groups = HashSet::from_iter(
-            vec![
-                IPos2::new(0, 0),
-                IPos2::new(0, 1),
-                IPos2::new(0, 2),
-                IPos2::new(0, 3),
-                IPos2::new(1, 3),
-                IPos2::new(2, 3),
-                IPos2::new(3, 3),
-            ]
-            .into_iter(),
+        assert_eq!(
+            circuit.wire_segments(),
+            [segment((0, 0), (0, 3)), segment((0, 3), (3, 3))]
         );
-        assert_eq!(circuit.node_groups, vec![test_groups]);
+
+        let test_groups = HashSet::from_iter(vec![
+            IPos2::new(0, 0),
+            IPos2::new(0, 1),
+            IPos2::new(0, 2),
+            IPos2::new(0, 3),
+            IPos2::new(1, 3),
+            IPos2::new(2, 3),
+            IPos2::new(3, 3),
+        ]);
+        assert_eq!(group_points(&circuit), vec![test_groups]);
     }
 
     #[test]
@@ -521,26 +1248,18 @@ mod tests {
         let mut circuit = GuiCircuit::default();
         circuit.add_orthogonal_wires(IPos2::new(2, 1), IPos2::new(0, 0), true);
 
-        assert_eq!(circuit.nodes_starts, [IPos2::new(0, 0), IPos2::new(2, 1)]);
-
-        let mut test_nodes = HashMap::new();
-        test_nodes.insert(IPos2::new(2, 1), vec![IPos2::new(1, 1)]);
-        test_nodes.insert(IPos2::new(1, 1), vec![IPos2::new(2, 1), IPos2::new(0, 1)]);
-        test_nodes.insert(IPos2::new(0, 1), vec![IPos2::new(1, 1), IPos2::new(0, 0)]);
-        test_nodes.insert(IPos2::new(0, 0), vec![IPos2::new(0, 1)]);
-
-        assert_eq!(circuit.nodes, test_nodes);
-
-        let test_groups = HashSet::from_iter(
-            vec![
-                IPos2::new(2, 1),
-                IPos2::new(1, 1),
-                IPos2::new(0, 1),
-                IPos2::new(0, 0),
-            ]
-            .into_iter(),
+        assert_eq!(
+            circuit.wire_segments(),
+            [segment((2, 1), (0, 1)), segment((0, 1), (0, 0))]
         );
-        assert_eq!(circuit.node_groups, vec![test_groups]);
+
+        let test_groups = HashSet::from_iter(vec![
+            IPos2::new(2, 1),
+            IPos2::new(1, 1),
+            IPos2::new(0, 1),
+            IPos2::new(0, 0),
+        ]);
+        assert_eq!(group_points(&circuit), vec![test_groups]);
     }
 
     #[test]
@@ -549,35 +1268,19 @@ mod tests {
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(2, 2), true);
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(2, 2), false);
 
-        let mut test_nodes = HashMap::new();
-        // X
-        test_nodes.insert(IPos2::new(0, 0), vec![IPos2::new(1, 0), IPos2::new(0, 1)]);
-        test_nodes.insert(IPos2::new(1, 0), vec![IPos2::new(0, 0), IPos2::new(2, 0)]);
-        test_nodes.insert(IPos2::new(2, 0), vec![IPos2::new(1, 0), IPos2::new(2, 1)]);
-        test_nodes.insert(IPos2::new(2, 1), vec![IPos2::new(2, 0), IPos2::new(2, 2)]);
-        test_nodes.insert(IPos2::new(2, 2), vec![IPos2::new(2, 1), IPos2::new(1, 2)]);
-
-        // Y
-        test_nodes.insert(IPos2::new(0, 1), vec![IPos2::new(0, 0), IPos2::new(0, 2)]);
-        test_nodes.insert(IPos2::new(0, 2), vec![IPos2::new(0, 1), IPos2::new(1, 2)]);
-        test_nodes.insert(IPos2::new(1, 2), vec![IPos2::new(0, 2), IPos2::new(2, 2)]);
-
-        assert_eq!(circuit.nodes, test_nodes);
-
-        let test_groups = HashSet::from_iter(
-            vec![
-                IPos2::new(0, 0),
-                IPos2::new(1, 0),
-                IPos2::new(0, 1),
-                IPos2::new(2, 0),
-                IPos2::new(0, 2),
-                IPos2::new(2, 1),
-                IPos2::new(1, 2),
-                IPos2::new(2, 2),
-            ]
-            .into_iter(),
-        );
-        assert_eq!(circuit.node_groups, vec![test_groups]);
+        assert_eq!(circuit.wire_segments().len(), 4);
+
+        let test_groups = HashSet::from_iter(vec![
+            IPos2::new(0, 0),
+            IPos2::new(1, 0),
+            IPos2::new(0, 1),
+            IPos2::new(2, 0),
+            IPos2::new(0, 2),
+            IPos2::new(2, 1),
+            IPos2::new(1, 2),
+            IPos2::new(2, 2),
+        ]);
+        assert_eq!(group_points(&circuit), vec![test_groups]);
     }
 
     #[test]
@@ -586,17 +1289,14 @@ mod tests {
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(1, 0), true);
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(2, 0), true);
 
-        let mut test_nodes = HashMap::new();
-        test_nodes.insert(IPos2::new(0, 0), vec![IPos2::new(1, 0)]);
-        test_nodes.insert(IPos2::new(1, 0), vec![IPos2::new(0, 0), IPos2::new(2, 0)]);
-        test_nodes.insert(IPos2::new(2, 0), vec![IPos2::new(1, 0)]);
-
-        assert_eq!(circuit.nodes, test_nodes);
-
-        let test_groups = HashSet::from_iter(
-            vec![IPos2::new(0, 0), IPos2::new(1, 0), IPos2::new(2, 0)].into_iter(),
+        assert_eq!(
+            circuit.wire_segments(),
+            [segment((0, 0), (1, 0)), segment((0, 0), (2, 0))]
         );
-        assert_eq!(circuit.node_groups, vec![test_groups]);
+
+        let test_groups =
+            HashSet::from_iter(vec![IPos2::new(0, 0), IPos2::new(1, 0), IPos2::new(2, 0)]);
+        assert_eq!(group_points(&circuit), vec![test_groups]);
     }
 
     #[test]
@@ -605,29 +1305,357 @@ mod tests {
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(1, 0), true);
         circuit.add_orthogonal_wires(IPos2::new(0, 1), IPos2::new(1, 1), true);
 
-        let mut test_nodes = HashMap::new();
-        test_nodes.insert(IPos2::new(0, 0), vec![IPos2::new(1, 0)]);
-        test_nodes.insert(IPos2::new(1, 0), vec![IPos2::new(0, 0)]);
-        test_nodes.insert(IPos2::new(0, 1), vec![IPos2::new(1, 1)]);
-        test_nodes.insert(IPos2::new(1, 1), vec![IPos2::new(0, 1)]);
-
-        assert_eq!(circuit.nodes, test_nodes);
+        assert_eq!(
+            circuit.wire_segments(),
+            [segment((0, 0), (1, 0)), segment((0, 1), (1, 1))]
+        );
 
-        let test_group1 = HashSet::from_iter(vec![IPos2::new(0, 1), IPos2::new(1, 1)].into_iter());
-        let test_group2 = HashSet::from_iter(vec![IPos2::new(0, 0), IPos2::new(1, 0)].into_iter());
-        assert_eq!(circuit.node_groups, vec![test_group1, test_group2]);
+        let test_group1 = HashSet::from_iter(vec![IPos2::new(0, 0), IPos2::new(1, 0)]);
+        let test_group2 = HashSet::from_iter(vec![IPos2::new(0, 1), IPos2::new(1, 1)]);
+        assert_eq!(group_points(&circuit), vec![test_group1, test_group2]);
     }
 
     #[test]
     fn remove_wires() {
         let mut circuit = GuiCircuit::default();
         circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(10, 0), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 5), IPos2::new(10, 5), true);
 
         circuit.remove_node(IPos2::new(0, 0));
 
-        assert_eq!(circuit.nodes, HashMap::new());
-        assert_eq!(circuit.node_groups, vec![]);
-        assert_eq!(circuit.nodes_starts, vec![]);
-        assert_eq!(circuit.rendered_wires, Vec::<Vec<Pos2>>::new());
+        assert_eq!(circuit.wire_segments(), [segment((0, 5), (10, 5))]);
+        assert_eq!(circuit.node_groups, vec![vec![0]]);
+        assert_eq!(circuit.node_group_index(IPos2::new(5, 5)), Some(0));
+        assert_eq!(circuit.node_group_index(IPos2::new(5, 0)), None);
+    }
+
+    #[test]
+    fn remove_wire_segment_splits_group() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(32, 32), true);
+        circuit.add_orthogonal_wires(IPos2::new(32, 32), IPos2::new(64, 32), true);
+        circuit.net_labels.push(NetLabel {
+            position: IPos2::new(32, 16),
+            name: "mid".to_string(),
+        });
+        assert_eq!(circuit.node_groups.len(), 1);
+        assert_eq!(circuit.wire_segments().len(), 3);
+
+        let segment = circuit.wire_segment_at(Pos2::new(34.0, 16.0), 4.0).unwrap();
+        assert_eq!(
+            segment,
+            WireSegment::new(IPos2::new(32, 32), IPos2::new(32, 0))
+        );
+        assert!(circuit.remove_wire_segment(segment));
+        assert!(!circuit.remove_wire_segment(segment));
+
+        assert_eq!(circuit.node_groups.len(), 2);
+        let group = |x: i32, y: i32| circuit.node_group_index(IPos2::new(x, y));
+        assert!(group(0, 0).is_some());
+        assert!(group(32, 16).is_none());
+        assert_eq!(group(32, 32), group(64, 32));
+        assert_ne!(group(0, 0), group(64, 32));
+        assert_eq!(group(32, 0), group(0, 0));
+        assert_eq!(circuit.wire_segments().len(), 2);
+        assert!(circuit.net_labels.is_empty());
+    }
+
+    #[test]
+    fn wire_index_follows_removals() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_orthogonal_wires(IPos2::new(-40, 0), IPos2::new(100, 50), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 200), IPos2::new(0, 120), true);
+        circuit.add_orthogonal_wires(IPos2::new(-20, 150), IPos2::new(0, 150), true);
+        let group =
+            |circuit: &GuiCircuit, x: i32, y: i32| circuit.node_group_index(IPos2::new(x, y));
+
+        // Points off the grid in the middle of long wires
+        assert_eq!(group(&circuit, -33, 0), group(&circuit, 100, 37));
+        assert_eq!(group(&circuit, -7, 150), group(&circuit, 0, 199));
+        assert_ne!(group(&circuit, 0, 0), group(&circuit, 0, 150));
+        assert_eq!(group(&circuit, 50, 50), None);
+
+        // The untouched group keeps its wires after the first group splits
+        assert!(circuit.remove_wire_segment(segment((100, 0), (-40, 0))));
+        assert_eq!(group(&circuit, 0, 0), None);
+        assert_eq!(group(&circuit, 100, 25), Some(0));
+        assert_eq!(group(&circuit, -20, 150), group(&circuit, 0, 120));
+        assert_eq!(circuit.node_groups, vec![vec![0], vec![1, 2]]);
+
+        // Loading builds the same groups
+        let mut loaded = GuiCircuit::default();
+        loaded.set_wires(circuit.wire_segments().to_vec(), vec![]);
+        assert_eq!(loaded.node_groups, circuit.node_groups);
+        assert_eq!(group(&loaded, -7, 150), Some(1));
+    }
+
+    #[test]
+    fn crossing_wires_need_a_junction() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_orthogonal_wires(IPos2::new(0, 32), IPos2::new(64, 32), true);
+        circuit.add_orthogonal_wires(IPos2::new(32, 0), IPos2::new(32, 64), true);
+        let group = |circuit: &GuiCircuit, x: i32, y: i32| {
+            circuit.node_group_index(IPos2::new(x, y)).unwrap()
+        };
+        assert_eq!(circuit.node_groups.len(), 2);
+        assert_ne!(group(&circuit, 0, 32), group(&circuit, 32, 0));
+        assert!(circuit.junction_dots().is_empty());
+        assert_eq!(
+            circuit.wire_segments()[0].crossing(&circuit.wire_segments()[1]),
+            Some(IPos2::new(32, 32))
+        );
+
+        assert!(!circuit.add_junction(IPos2::new(16, 16)));
+        assert!(circuit.add_junction(IPos2::new(32, 32)));
+        assert_eq!(circuit.node_groups.len(), 1);
+        assert_eq!(circuit.junction_dots(), [IPos2::new(32, 32)]);
+
+        assert!(circuit.remove_junction(IPos2::new(32, 32)));
+        assert_eq!(circuit.node_groups.len(), 2);
+
+        // A wire ending on another one is a T junction
+        circuit.add_orthogonal_wires(IPos2::new(64, 0), IPos2::new(64, 32), true);
+        assert_eq!(group(&circuit, 64, 0), group(&circuit, 0, 32));
+        assert_eq!(circuit.junction_dots(), []);
+        circuit.add_orthogonal_wires(IPos2::new(48, 32), IPos2::new(48, 64), true);
+        assert_eq!(circuit.junction_dots(), [IPos2::new(48, 32)]);
+    }
+
+    #[test]
+    fn wire_grid_points() {
+        let wire = segment((8, 16), (40, 16));
+        assert_eq!(
+            wire.grid_points().collect::<Vec<IPos2>>(),
+            [IPos2::new(16, 16), IPos2::new(32, 16)]
+        );
+        let wire = segment((-16, -20), (-16, 0));
+        assert_eq!(
+            wire.grid_points().collect::<Vec<IPos2>>(),
+            [IPos2::new(-16, -16), IPos2::new(-16, 0)]
+        );
+    }
+
+    #[test]
+    fn designators_per_prefix() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        circuit.add_element(new_element(ElementType::Capacitor(1.0)));
+        circuit.add_element(new_element(ElementType::Ground));
+        assert_eq!(element_names(&circuit), ["", "C1", "R1", "R2"]);
+
+        // Removing R1 keeps R2 and the next resistor reuses R1
+        let r1 = circuit
+            .gui_elements
+            .values()
+            .find(|x| x.name == "R1")
+            .unwrap()
+            .id();
+        circuit.remove_element(r1);
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        assert_eq!(element_names(&circuit), ["", "C1", "R1", "R2", "R3"]);
+    }
+
+    #[test]
+    fn designator_validation() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        let mut element = new_element(ElementType::Resistor(1.0));
+
+        element.name = "Rload".to_string();
+        assert!(circuit.validate_designator(1, &element).is_ok());
+        element.name = "r1".to_string();
+        assert!(circuit.validate_designator(1, &element).is_err());
+        element.name = "R1".to_string();
+        assert!(circuit.validate_designator(1, &element).is_err());
+        assert!(circuit.validate_designator(0, &element).is_ok());
+        element.name = "C5".to_string();
+        assert!(circuit.validate_designator(1, &element).is_err());
+        element.name = "R 2".to_string();
+        assert!(circuit.validate_designator(1, &element).is_err());
+
+        // Taken names are replaced when added
+        element.name = "R1".to_string();
+        circuit.add_element(element);
+        assert_eq!(element_names(&circuit), ["R1", "R2"]);
+    }
+
+    #[test]
+    fn net_labels_merge_groups() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(32, 0), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 64), IPos2::new(32, 64), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 128), IPos2::new(32, 128), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 192), IPos2::new(32, 192), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 256), IPos2::new(32, 256), true);
+        assert!(circuit.node_numbers().is_none());
+
+        let labels = [
+            ((0, 0), "out"),
+            ((32, 64), "out"),
+            ((16, 128), "GND"),
+            ((0, 256), "Out"),
+        ];
+        for (position, name) in labels {
+            circuit.net_labels.push(NetLabel {
+                position: IPos2::new(position.0, position.1),
+                name: name.to_string(),
+            });
+        }
+
+        let (node_numbers, node_count) = circuit.node_numbers().unwrap();
+        let node =
+            |x: i32, y: i32| node_numbers[circuit.node_group_index(IPos2::new(x, y)).unwrap()];
+        assert_eq!(node_count, 3);
+        assert_eq!(node(0, 0), node(0, 64));
+        assert_ne!(node(0, 0), 0);
+        assert_eq!(node(0, 128), 0);
+        assert_ne!(node(0, 192), node(0, 0));
+        // SPICE node names ignore case, so the exported deck can not keep them apart
+        assert_eq!(node(0, 256), node(0, 0));
+
+        let netlist = circuit.netlist().unwrap();
+        assert_eq!(netlist.node_labels[node(0, 0)], Some("out".to_string()));
+        assert_eq!(netlist.node_labels[node(0, 192)], None);
+    }
+
+    #[test]
+    fn net_label_names() {
+        assert!(NetLabel::validate_name("out").is_ok());
+        assert!(NetLabel::validate_name("0").is_ok());
+        assert!(NetLabel::validate_name("12").is_err());
+        assert!(NetLabel::validate_name("a b").is_err());
+        assert!(NetLabel::validate_name("").is_err());
+    }
+
+    #[test]
+    fn moved_element_keeps_connections() {
+        let mut circuit = GuiCircuit::default();
+        // Pins at (32, 64) and (96, 64)
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        circuit.add_orthogonal_wires(IPos2::new(32, 64), IPos2::new(32, 128), true);
+        circuit.add_orthogonal_wires(IPos2::new(96, 64), IPos2::new(96, 128), true);
+
+        assert!(circuit.move_element(0, Pos2::new(64.0, 0.0)));
+        assert_eq!(
+            circuit.gui_elements[&0].nodes,
+            [IPos2::new(32, 0), IPos2::new(96, 0)]
+        );
+        let group = |x: i32, y: i32| circuit.node_group_index(IPos2::new(x, y)).unwrap();
+        assert_eq!(group(32, 0), group(32, 128));
+        assert_eq!(group(96, 0), group(96, 128));
+        assert_ne!(group(32, 0), group(96, 0));
+
+        // Moving along its own axis would short the pins
+        assert!(!circuit.move_element(0, Pos2::new(128.0, 0.0)));
+    }
+
+    #[test]
+    fn stretched_wire_avoids_foreign_pins() {
+        let mut circuit = GuiCircuit::default();
+        // Pins at (32, 64) and (96, 64), then (96, 96) and (160, 96) on the stretched path
+        let moved = add_test_element(
+            &mut circuit,
+            ElementType::Resistor(1.0),
+            Pos2::new(64.0, 64.0),
+            0.0,
+        );
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(1.0),
+            Pos2::new(128.0, 96.0),
+            0.0,
+        );
+        circuit.add_orthogonal_wires(IPos2::new(96, 64), IPos2::new(96, 32), true);
+
+        // Running through the unconnected pin would connect it
+        let mut unconnected = circuit.clone();
+        assert!(!unconnected.move_element(moved, Pos2::new(64.0, 128.0)));
+
+        // Running over the end of the other net's wire would short both nets
+        circuit.add_orthogonal_wires(IPos2::new(96, 96), IPos2::new(64, 96), true);
+        assert!(!circuit.move_element(moved, Pos2::new(64.0, 128.0)));
+    }
+
+    #[test]
+    fn moved_elements_keep_connections() {
+        let mut circuit = GuiCircuit::default();
+        // Pins at (32, 64) and (96, 64), then (128, 64) and (192, 64)
+        circuit.add_element(new_element(ElementType::Resistor(1.0)));
+        let mut second = new_element(ElementType::Resistor(1.0));
+        second.rect = second.rect.translate(Vec2::new(96.0, 0.0));
+        second.nodes = second.pins_at(second.rect.center());
+        circuit.add_element(second);
+        circuit.add_orthogonal_wires(IPos2::new(96, 64), IPos2::new(128, 64), true);
+        circuit.add_orthogonal_wires(IPos2::new(192, 64), IPos2::new(192, 128), true);
+
+        assert!(circuit.move_elements(&[0, 1], Vec2::new(0.0, 64.0)));
+        assert_eq!(
+            circuit.gui_elements[&1].nodes,
+            [IPos2::new(128, 128), IPos2::new(192, 128)]
+        );
+        let group = |x: i32, y: i32| circuit.node_group_index(IPos2::new(x, y));
+        assert!(group(96, 128).is_some());
+        assert_eq!(group(96, 128), group(128, 128));
+        assert_eq!(group(192, 128), group(192, 64));
+        assert_ne!(group(128, 128), group(192, 128));
+    }
+
+    #[test]
+    fn stamps_number_branches_in_netlist_order() {
+        let netlist = numbered_netlist(
+            3,
+            vec![
+                element("V1", ElementType::DCVoltageSource(5.0), 1, 0),
+                element("R1", ElementType::Resistor(1000.0), 1, 2),
+                element("L1", ElementType::Inductor(1e-3), 2, 0),
+                element("I1", ElementType::DCCurrentSource(1e-3), 0, 2),
+                element("V2", ElementType::DCVoltageSource(1.0), 2, 0),
+            ],
+        );
+
+        let branches = netlist
+            .stamps()
+            .iter()
+            .map(|x| x.and_then(|x| x.branch()))
+            .collect::<Vec<Option<usize>>>();
+        assert_eq!(branches, [Some(0), None, Some(1), None, Some(2)]);
+        assert_eq!(netlist.branch_count(), 3);
+        assert_eq!(netlist.stamps()[1], Some(Stamp::Resistor(1000.0)));
+    }
+
+    #[test]
+    fn rotate_and_flip() {
+        let mut resistor = new_element(ElementType::Resistor(1.0));
+        let pins = resistor.nodes.clone();
+        assert_eq!(pins, [IPos2::new(32, 64), IPos2::new(96, 64)]);
+
+        resistor.rotate_quarter();
+        assert_eq!(resistor.nodes[0].x, resistor.nodes[1].x);
+        for _ in 0..3 {
+            resistor.rotate_quarter();
+        }
+        assert_eq!(resistor.nodes, pins);
+
+        resistor.flip_horizontal();
+        assert_eq!(resistor.nodes, [pins[1], pins[0]]);
+        assert_eq!(resistor.flip(), (true, false));
+        resistor.flip_horizontal();
+        assert_eq!(resistor.nodes, pins);
+
+        resistor.flip_vertical();
+        assert_eq!(resistor.nodes, pins);
+
+        // A vertical element mirrored left to right keeps its pin order
+        resistor.flip_vertical();
+        resistor.rotate_quarter();
+        let vertical_pins = resistor.nodes.clone();
+        resistor.flip_horizontal();
+        assert_eq!(resistor.nodes, vertical_pins);
+
+        let mut ground = new_element(ElementType::Ground);
+        ground.flip_vertical();
+        assert_eq!(ground.nodes, [IPos2::new(64, 80)]);
     }
 }
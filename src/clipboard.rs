@@ -0,0 +1,300 @@
+use egui::{Context, Event, Pos2, Vec2};
+use hashbrown::HashMap;
+
+use crate::{
+    app::SpiceGuIrsApp,
+    circuit::{is_ground_label, GuiCircuit, NetLabel, WireSegment},
+    schematic_file::SchematicFile,
+    utils::ipos2::{IPos2, Pos2Ext},
+    GRID_SIZE,
+};
+
+/// Handles the copy, cut and paste events egui creates for Ctrl+C, Ctrl+X and Ctrl+V
+pub fn handle_clipboard(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    let events = ctx.input(|input| input.events.clone());
+    for event in events {
+        match event {
+            Event::Copy => copy_selection(app, ctx),
+            Event::Cut => cut_selection(app, ctx),
+            Event::Paste(text) => {
+                let position = app.canvas.pointer_position(ctx);
+                paste(app, &text, position);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Copies the selected elements and the wires between them to the system clipboard as
+/// schematic text
+pub fn copy_selection(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let wires = copied_wires(
+        &app.gui_circuit,
+        &app.selected_elements,
+        &app.selected_wires,
+    );
+    if app.selected_elements.is_empty() && wires.is_empty() {
+        return;
+    }
+
+    let fragment = fragment(&app.gui_circuit, &app.selected_elements, &wires);
+    match SchematicFile::from_circuit(&fragment).to_json() {
+        Ok(text) => {
+            ctx.copy_text(text.clone());
+            app.clipboard = Some(text);
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+/// Copies the selection and removes it together with the copied wires
+pub fn cut_selection(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let wires = copied_wires(
+        &app.gui_circuit,
+        &app.selected_elements,
+        &app.selected_wires,
+    );
+    if app.selected_elements.is_empty() && wires.is_empty() {
+        return;
+    }
+    copy_selection(app, ctx);
+
+    app.history.push(app.gui_circuit.clone());
+    app.selected_wires = wires;
+    app.remove_selection();
+}
+
+/// Pastes schematic text with its top left corner at `position`, or next to where it was copied
+/// from without one. The pasted elements become the selection.
+pub fn paste(app: &mut SpiceGuIrsApp, text: &str, position: Option<Pos2>) {
+    let fragment = match SchematicFile::from_json(text).and_then(|x| x.into_circuit()) {
+        Ok(fragment) => fragment,
+        Err(error) => {
+            app.terminal_lines.push(format!("Error: {}", error));
+            return;
+        }
+    };
+    let Some(origin) = fragment_origin(&fragment) else {
+        return;
+    };
+    let offset = match position {
+        Some(position) => position.to_ipos2(GRID_SIZE).to_pos2() - origin,
+        None => Vec2::splat(2.0 * GRID_SIZE as f32),
+    };
+
+    app.history.push(app.gui_circuit.clone());
+    let ids = paste_fragment(&mut app.gui_circuit, &fragment, offset);
+    app.clear_selection();
+    app.selected_elements = ids;
+}
+
+/// Wire segments copied along with the selected elements, the selected segments and every
+/// segment of the groups that only connect pins of selected elements
+pub fn copied_wires(
+    gui_circuit: &GuiCircuit,
+    elements: &[u32],
+    wires: &[WireSegment],
+) -> Vec<WireSegment> {
+    let internal_groups = (0..gui_circuit.node_groups.len())
+        .filter(|group| {
+            let pins = gui_circuit
+                .gui_elements
+                .values()
+                .filter(|x| {
+                    x.nodes
+                        .iter()
+                        .any(|pin| gui_circuit.node_group_index(*pin) == Some(*group))
+                })
+                .map(|x| x.id())
+                .collect::<Vec<u32>>();
+
+            !pins.is_empty() && pins.iter().all(|x| elements.contains(x))
+        })
+        .collect::<Vec<usize>>();
+
+    let mut copied = wires.to_vec();
+    for segment in gui_circuit.wire_segments().iter().copied() {
+        let internal = gui_circuit
+            .node_group_index(segment.start)
+            .is_some_and(|x| internal_groups.contains(&x));
+        if internal && !copied.contains(&segment) {
+            copied.push(segment);
+        }
+    }
+
+    copied
+}
+
+/// Circuit holding copies of `elements` and `wires` with the junctions and net labels on them
+pub fn fragment(gui_circuit: &GuiCircuit, elements: &[u32], wires: &[WireSegment]) -> GuiCircuit {
+    let mut fragment = GuiCircuit::default();
+    for id in elements {
+        if let Some(gui_element) = gui_circuit.gui_elements.get(id) {
+            fragment.add_element(gui_element.clone());
+        }
+    }
+
+    for segment in wires {
+        fragment.add_orthogonal_wires(segment.start, segment.end, true);
+    }
+    for junction in gui_circuit.junctions() {
+        if wires.iter().any(|x| x.contains(*junction)) {
+            fragment.add_junction(*junction);
+        }
+    }
+    fragment.net_labels = gui_circuit
+        .net_labels
+        .iter()
+        .filter(|x| {
+            wires
+                .iter()
+                .any(|y| y.rect().contains(x.position.to_pos2()))
+        })
+        .cloned()
+        .collect();
+
+    fragment
+}
+
+/// Grid point at the top left corner of a fragment
+fn fragment_origin(fragment: &GuiCircuit) -> Option<Pos2> {
+    let points = fragment
+        .gui_elements
+        .values()
+        .map(|x| x.rect.min)
+        .chain(fragment.wire_segments().iter().map(|x| x.start.to_pos2()));
+
+    points
+        .reduce(|a, b| a.min(b))
+        .map(|x| x.to_ipos2(GRID_SIZE).to_pos2())
+}
+
+/// Adds the contents of `fragment` moved by `offset` and returns the ids of the new elements
+pub fn paste_fragment(
+    gui_circuit: &mut GuiCircuit,
+    fragment: &GuiCircuit,
+    offset: Vec2,
+) -> Vec<u32> {
+    let mut elements = fragment.gui_elements.values().collect::<Vec<_>>();
+    elements.sort_by_key(|x| x.id());
+
+    let ids = elements
+        .into_iter()
+        .map(|gui_element| {
+            let mut gui_element = gui_element.clone();
+            gui_element.rect = gui_element.rect.translate(offset);
+            gui_element.nodes = gui_element.pins_at(gui_element.rect.center());
+            gui_circuit.add_element(gui_element)
+        })
+        .collect();
+
+    let translate = |x: IPos2| (x.to_pos2() + offset).to_ipos2(1);
+    for segment in fragment.wire_segments() {
+        gui_circuit.add_orthogonal_wires(translate(segment.start), translate(segment.end), true);
+    }
+    for junction in fragment.junctions() {
+        gui_circuit.add_junction(translate(*junction));
+    }
+    // Labels keep their name only while it is free, otherwise the copy would be connected to the
+    // original net. Labels sharing a name in the fragment keep sharing their new name.
+    let mut names: HashMap<String, String> = HashMap::new();
+    for net_label in fragment.net_labels.iter() {
+        let name = match names.get(&net_label.name.to_ascii_lowercase()) {
+            Some(name) => name.clone(),
+            None if is_ground_label(&net_label.name) => net_label.name.clone(),
+            None => {
+                let in_use = gui_circuit
+                    .net_labels
+                    .iter()
+                    .any(|x| x.name.eq_ignore_ascii_case(&net_label.name));
+                let name = match in_use {
+                    true => gui_circuit.next_net_label_name(),
+                    false => net_label.name.clone(),
+                };
+                names.insert(net_label.name.to_ascii_lowercase(), name.clone());
+                name
+            }
+        };
+
+        gui_circuit.net_labels.push(NetLabel {
+            position: translate(net_label.position),
+            name,
+        });
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Pos2, Vec2};
+
+    use crate::{
+        circuit::{add_test_element, ElementType, GuiCircuit, NetLabel, WireSegment},
+        schematic_file::SchematicFile,
+        utils::ipos2::IPos2,
+    };
+
+    use super::{copied_wires, fragment, paste_fragment};
+
+    const RESISTOR: ElementType = ElementType::Resistor(100.0);
+
+    #[test]
+    fn copy_and_paste_fragment() {
+        let mut circuit = GuiCircuit::default();
+        // Pins at (32, 64), (96, 64) then (128, 64), (192, 64) then (224, 64), (288, 64)
+        let first = add_test_element(&mut circuit, RESISTOR, Pos2::new(64.0, 64.0), 0.0);
+        let second = add_test_element(&mut circuit, RESISTOR, Pos2::new(160.0, 64.0), 0.0);
+        add_test_element(&mut circuit, RESISTOR, Pos2::new(256.0, 64.0), 0.0);
+        circuit.add_orthogonal_wires(IPos2::new(96, 64), IPos2::new(128, 64), true);
+        circuit.add_orthogonal_wires(IPos2::new(192, 64), IPos2::new(224, 64), true);
+        for (position, name) in [((112, 64), "mid"), ((96, 64), "MID"), ((128, 64), "GND")] {
+            circuit.net_labels.push(NetLabel {
+                position: IPos2::new(position.0, position.1),
+                name: name.to_string(),
+            });
+        }
+
+        // Only the wire between the two selected resistors comes along
+        let wires = copied_wires(&circuit, &[first, second], &[]);
+        assert_eq!(
+            wires,
+            [WireSegment::new(IPos2::new(96, 64), IPos2::new(128, 64))]
+        );
+
+        let copied = fragment(&circuit, &[first, second], &wires);
+        assert_eq!(copied.gui_elements.len(), 2);
+        assert_eq!(copied.node_groups.len(), 1);
+
+        // The fragment survives the trip through the clipboard text
+        let text = SchematicFile::from_circuit(&copied).to_json().unwrap();
+        let copied = SchematicFile::from_json(&text)
+            .unwrap()
+            .into_circuit()
+            .unwrap();
+
+        let ids = paste_fragment(&mut circuit, &copied, Vec2::new(0.0, 128.0));
+        assert_eq!(ids, [3, 4]);
+        assert_eq!(circuit.gui_elements[&3].name, "R4");
+        assert_eq!(
+            circuit.gui_elements[&4].nodes,
+            [IPos2::new(128, 192), IPos2::new(192, 192)]
+        );
+        let group = |x: i32, y: i32| circuit.node_group_index(IPos2::new(x, y));
+        assert!(group(96, 192).is_some());
+        assert_eq!(group(96, 192), group(128, 192));
+        assert_ne!(group(96, 192), group(96, 64));
+
+        // Taken names are replaced so the copy is not connected to the original net, ground
+        // labels keep connecting to ground
+        let names = circuit.net_labels[3..]
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, ["N1", "N1", "GND"]);
+    }
+}
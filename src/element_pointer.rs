@@ -1,33 +1,179 @@
 use core::f32;
+use std::sync::Arc;
+
+use hashbrown::HashSet;
 
 use egui::{
-    Button, Color32, Context, ImageButton, Key, PointerButton, Pos2, Rect, Sense, Shape, Stroke,
-    Ui, Vec2,
+    Align2, Area, Button, Color32, Context, FontId, Galley, Id, ImageButton, Key, PointerButton,
+    Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2,
 };
 
 use crate::{
-    app::{DragData, SpiceGuIrsApp},
-    circuit::GuiElement,
-    utils::ipos2::{IPos2, Pos2Ext},
+    app::{DragData, ElementDrag, SpiceGuIrsApp},
+    canvas::{canvas_layer, display_grid, handle_pan_zoom},
+    circuit::{image_flip, image_rotation, oriented_image, GuiElement, NetLabel, WireSegment},
+    erc::Severity,
+    utils::{ipos2::Pos2Ext, si::format_si},
     GRID_SIZE,
 };
 
 pub fn handle_elements(app: &mut SpiceGuIrsApp, ctx: &Context) {
-    egui::CentralPanel::default().show(&ctx, |ui| {
-        display_circuit_elements(app, ui);
-        display_to_place_element(app, ui);
-        display_dragged_wire(app, ui);
-        display_wire_drag_points(app, ui);
-        display_placed_wire(app, ui);
-        place_element(app, ui);
-        place_wires(app);
+    egui::CentralPanel::default().show(ctx, |ui| {
+        handle_pan_zoom(app, ui);
+        display_grid(app, ui);
+
+        // Everything below is in world coordinates, egui transforms the layer and its input
+        let layer = canvas_layer();
+        Area::new(layer.id)
+            .order(layer.order)
+            .fixed_pos(Pos2::ZERO)
+            .constrain(false)
+            .movable(false)
+            .show(ctx, |ui| {
+                ui.set_clip_rect(app.canvas.visible_rect());
+                ui.expand_to_include_rect(app.canvas.visible_rect());
+
+                start_marquee(app, ui);
+                display_circuit_elements(app, ui);
+                display_to_place_element(app, ui);
+                display_dragged_element(app, ui);
+                display_dragged_wire(app, ui);
+                display_wire_drag_points(app, ui);
+                display_placed_wire(app, ui);
+                display_selection(app, ui);
+                display_operating_point(app, ui);
+                display_erc_violations(app, ui);
+                display_sensitivity(app, ui);
+                display_net_labels(app, ui);
+                display_to_place_net_label(app, ui);
+                display_marquee(app, ui);
+                place_element(app, ui);
+                place_wires(app);
+            });
+        ctx.set_transform_layer(layer, app.canvas.transform);
+        ctx.set_sublayer(ui.layer_id(), layer);
     });
 
     rotate_to_place_element(app, ctx);
+    orient_selected_element(app, ctx);
     deselect_element(app, ctx);
     reset_dragged_node(app, ctx);
-    delete_selected_element(app, ctx);
-    delete_selected_node(app, ctx);
+    move_dragged_element(app, ctx);
+    select_in_marquee(app, ctx);
+    delete_selection(app, ctx);
+}
+
+/// Distance from a wire that still counts as clicking it
+const WIRE_CLICK_TOLERANCE: f32 = 4.0;
+
+/// Dragging the empty canvas starts a selection box, clicking it selects the wire segment under
+/// the pointer or clears the selection
+fn start_marquee(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let response = ui.interact(
+        app.canvas.visible_rect(),
+        Id::new("schematic_background"),
+        Sense::click_and_drag(),
+    );
+    let placing = app.to_place_element.is_some() || app.placing_net_label || app.placing_junction;
+    if placing || app.canvas.panning {
+        return;
+    }
+
+    if response.drag_started_by(PointerButton::Primary) {
+        app.marquee_start = response.interact_pointer_pos();
+    }
+    if response.clicked_by(PointerButton::Primary) {
+        let extend = ui.input(|x| x.modifiers.shift);
+        let segment = response
+            .interact_pointer_pos()
+            .and_then(|x| app.gui_circuit.wire_segment_at(x, WIRE_CLICK_TOLERANCE));
+        match segment {
+            Some(segment) => app.select_wire(segment, extend),
+            None if !extend => app.clear_selection(),
+            None => {}
+        }
+    }
+}
+
+fn display_marquee(app: &SpiceGuIrsApp, ui: &mut Ui) {
+    let Some(start) = app.marquee_start else {
+        return;
+    };
+    let Some(pointer_position) = app.canvas.pointer_position(ui.ctx()) else {
+        return;
+    };
+
+    ui.painter().rect(
+        Rect::from_two_pos(start, pointer_position),
+        0.0,
+        Color32::LIGHT_BLUE.gamma_multiply(0.1),
+        Stroke::new(1.0, Color32::LIGHT_BLUE),
+    );
+}
+
+/// Selects the elements and wire segments touched by the selection box once it is released,
+/// holding shift adds them to the current selection
+fn select_in_marquee(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let Some(start) = app.marquee_start else {
+        return;
+    };
+    let (released, pointer_position, extend) = ctx.input(|input| {
+        (
+            input.pointer.button_released(PointerButton::Primary),
+            input.pointer.latest_pos().map(|x| app.canvas.to_world(x)),
+            input.modifiers.shift,
+        )
+    });
+    if !released {
+        return;
+    }
+    app.marquee_start = None;
+
+    let Some(pointer_position) = pointer_position else {
+        return;
+    };
+    let marquee = Rect::from_two_pos(start, pointer_position);
+    if !extend {
+        app.clear_selection();
+    }
+
+    let mut elements = app
+        .gui_circuit
+        .gui_elements
+        .values()
+        .filter(|x| x.rect.intersects(marquee))
+        .map(|x| x.id())
+        .collect::<Vec<u32>>();
+    elements.sort();
+    for id in elements {
+        if !app.selected_elements.contains(&id) {
+            app.selected_elements.push(id);
+        }
+    }
+
+    for segment in app.gui_circuit.wire_segments().iter().copied() {
+        if segment.rect().intersects(marquee) && !app.selected_wires.contains(&segment) {
+            app.selected_wires.push(segment);
+        }
+    }
+}
+
+fn display_selection(app: &SpiceGuIrsApp, ui: &mut Ui) {
+    let color = Color32::LIGHT_BLUE;
+
+    for id in app.selected_elements.iter() {
+        if let Some(gui_element) = app.gui_circuit.gui_elements.get(id) {
+            ui.painter()
+                .rect_stroke(gui_element.rect, 4.0, Stroke::new(1.0, color));
+        }
+    }
+
+    for segment in app.selected_wires.iter() {
+        ui.painter().line_segment(
+            [segment.start.to_pos2(), segment.end.to_pos2()],
+            Stroke::new(3.0, color),
+        );
+    }
 }
 
 fn display_to_place_element(app: &SpiceGuIrsApp, ui: &mut Ui) {
@@ -35,41 +181,167 @@ fn display_to_place_element(app: &SpiceGuIrsApp, ui: &mut Ui) {
         return;
     };
 
-    let pointer_position = ui
-        .ctx()
-        .pointer_latest_pos()
+    let pointer_position = app
+        .canvas
+        .pointer_position(ui.ctx())
         .unwrap_or_default()
         .to_ipos2(GRID_SIZE)
         .to_pos2();
 
-    let rect = Rect::from_center_size(
-        pointer_position,
-        element_image.calc_size(Vec2::new(128.0, 62.5), element_image.size()),
-    );
+    let rect = GuiElement::rect_at(&element_image, pointer_position);
 
     ui.put(rect, element_image);
 }
 
+/// Center a dragged element snaps to for the pointer at `pointer_position`
+fn dragged_element_center(element_drag: &ElementDrag, pointer_position: Pos2) -> Pos2 {
+    (pointer_position + element_drag.offset)
+        .to_ipos2(GRID_SIZE)
+        .to_pos2()
+}
+
+/// Elements moved by a drag, the whole selection if the dragged element is part of it
+fn dragged_elements(app: &SpiceGuIrsApp, element_drag: &ElementDrag) -> Vec<u32> {
+    match app.selected_elements.contains(&element_drag.id) {
+        true => app.selected_elements.clone(),
+        false => vec![element_drag.id],
+    }
+}
+
+fn display_dragged_element(app: &SpiceGuIrsApp, ui: &mut Ui) {
+    let Some(element_drag) = &app.element_drag else {
+        return;
+    };
+    let Some(dragged_element) = app.gui_circuit.gui_elements.get(&element_drag.id) else {
+        return;
+    };
+    let Some(pointer_position) = app.canvas.pointer_position(ui.ctx()) else {
+        return;
+    };
+
+    let offset =
+        dragged_element_center(element_drag, pointer_position) - dragged_element.rect.center();
+    let ids = dragged_elements(app, element_drag);
+    for id in ids.iter() {
+        let Some(gui_element) = app.gui_circuit.gui_elements.get(id) else {
+            continue;
+        };
+
+        ui.put(
+            gui_element.rect.translate(offset),
+            gui_element
+                .image
+                .clone()
+                .tint(Color32::from_white_alpha(128)),
+        );
+    }
+
+    // Preview of the wires stretched to the new pins, routed by the same move that runs on
+    // release. A move that would be rejected shows no wires.
+    let mut moved = app.gui_circuit.clone();
+    if !moved.move_elements(&ids, offset) {
+        return;
+    }
+    let wires = app
+        .gui_circuit
+        .wire_segments()
+        .iter()
+        .collect::<HashSet<&WireSegment>>();
+    let stroke = Stroke::new(2.0, Color32::BLUE);
+    for wire in moved.wire_segments().iter().filter(|x| !wires.contains(x)) {
+        ui.painter()
+            .line_segment([wire.start.to_pos2(), wire.end.to_pos2()], stroke);
+    }
+}
+
+fn move_dragged_element(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let Some(element_drag) = app.element_drag else {
+        return;
+    };
+    let (released, pointer_position) = ctx.input(|input| {
+        (
+            input.pointer.button_released(PointerButton::Primary),
+            input.pointer.latest_pos().map(|x| app.canvas.to_world(x)),
+        )
+    });
+    if !released {
+        return;
+    }
+    app.element_drag = None;
+
+    let Some(pointer_position) = pointer_position else {
+        return;
+    };
+    let Some(gui_element) = app.gui_circuit.gui_elements.get(&element_drag.id) else {
+        return;
+    };
+    let offset =
+        dragged_element_center(&element_drag, pointer_position) - gui_element.rect.center();
+    if offset == Vec2::ZERO {
+        return;
+    }
+
+    let ids = dragged_elements(app, &element_drag);
+    let snapshot = app.gui_circuit.clone();
+    if app.gui_circuit.move_elements(&ids, offset) {
+        app.history.push(snapshot);
+    } else {
+        let name = match ids.len() {
+            1 => snapshot.gui_elements[&element_drag.id].name.clone(),
+            _ => "the selected elements".to_string(),
+        };
+        app.gui_circuit = snapshot;
+        app.terminal_lines.push(format!(
+            "Error: Can not move {} there without connecting it to another net",
+            name
+        ));
+    }
+}
+
 fn display_circuit_elements(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let extend = ui.input(|x| x.modifiers.shift);
+    let mut clicked = None;
     for (index, gui_element) in app.gui_circuit.gui_elements.iter() {
-        let response = ui.put(
-            gui_element.rect,
-            ImageButton::new(gui_element.image.clone()).frame(false),
+        let response = ui
+            .put(
+                gui_element.rect,
+                ImageButton::new(gui_element.image.clone()).frame(false),
+            )
+            .interact(Sense::click_and_drag());
+        ui.painter().text(
+            gui_element.rect.center_top(),
+            Align2::CENTER_TOP,
+            &gui_element.name,
+            FontId::proportional(12.0),
+            ui.visuals().text_color(),
         );
 
         if response.clicked_by(PointerButton::Primary) {
-            app.selected_element = Some(*index);
-            app.selected_node = None;
+            clicked = Some(*index);
             println!("Selected {}", index);
         }
 
+        let placing =
+            app.to_place_element.is_some() || app.placing_net_label || app.placing_junction;
+        if response.drag_started_by(PointerButton::Primary) && !placing && !app.canvas.panning {
+            if !app.selected_elements.contains(index) {
+                clicked = Some(*index);
+            }
+            if let Some(pointer_position) = response.interact_pointer_pos() {
+                app.element_drag = Some(ElementDrag {
+                    id: *index,
+                    offset: gui_element.rect.center() - pointer_position,
+                });
+            }
+        }
+
         for node_position in gui_element.nodes.iter() {
             let rect = Rect::from_center_size(node_position.to_pos2(), Vec2::splat(10.0));
             let response = ui
                 .put(rect, Button::new("").fill(Color32::from_white_alpha(0)))
                 .interact(Sense::click_and_drag());
 
-            if response.drag_started_by(PointerButton::Primary) {
+            if response.drag_started_by(PointerButton::Primary) && !app.canvas.panning {
                 app.drag_data = Some(DragData::new(rect.center().to_ipos2(GRID_SIZE)));
             }
 
@@ -88,6 +360,10 @@ fn display_circuit_elements(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
             }
         }
     }
+
+    if let Some(index) = clicked {
+        app.select_element(index, extend);
+    }
 }
 
 fn display_dragged_wire(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
@@ -96,9 +372,9 @@ fn display_dragged_wire(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
     };
 
     let start = drag_data.start_position.to_pos2();
-    let end = ui
-        .ctx()
-        .pointer_latest_pos()
+    let end = app
+        .canvas
+        .pointer_position(ui.ctx())
         .unwrap_or_default()
         .to_ipos2(GRID_SIZE)
         .to_pos2();
@@ -122,48 +398,268 @@ fn display_dragged_wire(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
 }
 
 fn display_wire_drag_points(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
-    for group in app.gui_circuit.node_groups.iter() {
-        for position in group.iter() {
-            if *position % GRID_SIZE != IPos2::ZERO {
-                continue;
-            }
-
-            let rect = Rect::from_center_size(position.to_pos2(), Vec2::splat(10.0));
-            let response = ui
-                .put(rect, Button::new("").fill(Color32::from_white_alpha(0)))
-                .interact(Sense::click_and_drag());
+    let extend = ui.input(|x| x.modifiers.shift);
+    let mut placed_net_label = None;
+    let mut toggled_junction = None;
+    let mut selected_wire = None;
+    for position in app.gui_circuit.wire_grid_points().iter() {
+        let rect = Rect::from_center_size(position.to_pos2(), Vec2::splat(10.0));
+        let response = ui
+            .put(rect, Button::new("").fill(Color32::from_white_alpha(0)))
+            .interact(Sense::click_and_drag());
 
-            if response.clicked_by(PointerButton::Primary) {
-                app.selected_node = Some(*position);
-                app.selected_element = None;
+        if response.clicked_by(PointerButton::Primary) {
+            if app.placing_net_label {
+                placed_net_label = Some(*position);
+            } else if app.placing_junction {
+                toggled_junction = Some(*position);
+            } else {
+                selected_wire = response
+                    .interact_pointer_pos()
+                    .and_then(|x| app.gui_circuit.wire_segment_at(x, WIRE_CLICK_TOLERANCE));
             }
+        }
 
-            if response.drag_started_by(PointerButton::Primary) {
-                app.drag_data = Some(DragData::new(rect.center().to_ipos2(GRID_SIZE)));
-            }
+        if response.drag_started_by(PointerButton::Primary) && !app.canvas.panning {
+            app.drag_data = Some(DragData::new(rect.center().to_ipos2(GRID_SIZE)));
+        }
 
-            if response.contains_pointer() && !response.drag_stopped_by(PointerButton::Primary) {
-                response.highlight();
+        if response.contains_pointer() && !response.drag_stopped_by(PointerButton::Primary) {
+            response.highlight();
 
-                let mut drag_stopped = false;
-                ui.input(|input| {
-                    drag_stopped = input.pointer.button_released(PointerButton::Primary)
-                });
-                if drag_stopped {
-                    if let Some(drag_data) = &mut app.drag_data {
-                        drag_data.end_position = Some(rect.center().to_ipos2(GRID_SIZE));
-                    }
+            let mut drag_stopped = false;
+            ui.input(|input| drag_stopped = input.pointer.button_released(PointerButton::Primary));
+            if drag_stopped {
+                if let Some(drag_data) = &mut app.drag_data {
+                    drag_data.end_position = Some(rect.center().to_ipos2(GRID_SIZE));
                 }
             }
         }
     }
+
+    if let Some(segment) = selected_wire {
+        app.select_wire(segment, extend);
+    }
+    if let Some(position) = toggled_junction {
+        let mut edited = app.gui_circuit.clone();
+        if edited.remove_junction(position) || edited.add_junction(position) {
+            app.history
+                .push(std::mem::replace(&mut app.gui_circuit, edited));
+        }
+    }
+    if let Some(position) = placed_net_label {
+        app.history.push(app.gui_circuit.clone());
+        app.gui_circuit.net_labels.push(NetLabel {
+            position,
+            name: app.gui_circuit.next_net_label_name(),
+        });
+        app.placing_net_label = false;
+        app.select_net_label(app.gui_circuit.net_labels.len() - 1);
+    }
+}
+
+/// Rect of a net label's name, drawn up and right of the wire point it is attached to
+fn net_label_rect(ui: &Ui, net_label: &NetLabel) -> (Rect, Arc<Galley>) {
+    let galley = ui.painter().layout_no_wrap(
+        net_label.name.clone(),
+        FontId::monospace(12.0),
+        ui.visuals().text_color(),
+    );
+    let rect = Rect::from_min_size(
+        net_label.position.to_pos2() + Vec2::new(4.0, -4.0 - galley.size().y),
+        galley.size(),
+    )
+    .expand(2.0);
+
+    (rect, galley)
+}
+
+fn display_net_labels(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let mut clicked = None;
+    for (index, net_label) in app.gui_circuit.net_labels.iter().enumerate() {
+        let (rect, galley) = net_label_rect(ui, net_label);
+        let response = ui.interact(rect, Id::new(("net_label", index)), Sense::click());
+        if response.clicked_by(PointerButton::Primary) {
+            clicked = Some(index);
+        }
+
+        let color = match app.selected_net_label == Some(index) || response.hovered() {
+            true => Color32::LIGHT_BLUE,
+            false => Color32::GRAY,
+        };
+        let painter = ui.painter();
+        painter.rect(
+            rect,
+            2.0,
+            ui.visuals().extreme_bg_color,
+            Stroke::new(1.0, color),
+        );
+        painter.galley(rect.min + Vec2::splat(2.0), galley, color);
+        painter.circle_filled(net_label.position.to_pos2(), 3.0, color);
+    }
+
+    if let Some(index) = clicked {
+        app.select_net_label(index);
+    }
+}
+
+fn display_to_place_net_label(app: &SpiceGuIrsApp, ui: &mut Ui) {
+    if !app.placing_net_label {
+        return;
+    }
+    let Some(pointer_position) = app.canvas.pointer_position(ui.ctx()) else {
+        return;
+    };
+
+    let net_label = NetLabel {
+        position: pointer_position.to_ipos2(GRID_SIZE),
+        name: app.gui_circuit.next_net_label_name(),
+    };
+    let (rect, galley) = net_label_rect(ui, &net_label);
+    ui.painter()
+        .rect_stroke(rect, 2.0, Stroke::new(1.0, Color32::BLUE));
+    ui.painter()
+        .galley(rect.min + Vec2::splat(2.0), galley, Color32::BLUE);
 }
 
 fn display_placed_wire(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
     let stroke = Stroke::new(2.0, Color32::WHITE);
 
-    for wire in app.gui_circuit.rendered_wires.iter() {
-        ui.painter().add(Shape::line(wire.clone(), stroke));
+    for wire in app.gui_circuit.wire_segments().iter() {
+        ui.painter()
+            .line_segment([wire.start.to_pos2(), wire.end.to_pos2()], stroke);
+    }
+    for position in app.gui_circuit.junction_dots() {
+        ui.painter()
+            .circle_filled(position.to_pos2(), 4.0, Color32::WHITE);
+    }
+}
+
+fn display_operating_point(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    if app
+        .operating_point
+        .as_ref()
+        .is_some_and(|x| x.revision != app.history.revision())
+    {
+        app.operating_point = None;
+    }
+    if !app.show_operating_point {
+        return;
+    }
+    let Some(operating_point) = &app.operating_point else {
+        return;
+    };
+
+    let font = FontId::proportional(12.0);
+    let color = Color32::LIGHT_GREEN;
+
+    for (group, voltage) in operating_point.node_voltages.iter().enumerate() {
+        let Some((name, voltage)) = voltage else {
+            continue;
+        };
+        // Label the top left corner of the wires so it does not cover elements
+        let Some(position) = app
+            .gui_circuit
+            .group_wires(group)
+            .map(|x| x.start)
+            .min_by_key(|x| (x.y, x.x))
+        else {
+            continue;
+        };
+
+        ui.painter().text(
+            position.to_pos2() + Vec2::new(4.0, -4.0),
+            Align2::LEFT_BOTTOM,
+            format!("{} = {}", name, format_si(*voltage, "V")),
+            font.clone(),
+            color,
+        );
+    }
+
+    for (id, (name, current)) in operating_point.branch_currents.iter() {
+        let Some(gui_element) = app.gui_circuit.gui_elements.get(id) else {
+            continue;
+        };
+
+        ui.painter().text(
+            gui_element.rect.center_bottom(),
+            Align2::CENTER_TOP,
+            format!("{} = {}", name, format_si(*current, "A")),
+            font.clone(),
+            color,
+        );
+    }
+}
+
+fn display_erc_violations(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    if app
+        .erc_report
+        .as_ref()
+        .is_some_and(|x| x.revision != app.history.revision())
+    {
+        app.erc_report = None;
+    }
+    let Some(erc_report) = &app.erc_report else {
+        return;
+    };
+
+    for violation in erc_report.violations.iter() {
+        let color = match violation.severity {
+            Severity::Warning => Color32::from_rgb(255, 165, 0),
+            Severity::Error => Color32::RED,
+        };
+
+        for id in violation.elements.iter() {
+            if let Some(gui_element) = app.gui_circuit.gui_elements.get(id) {
+                ui.painter()
+                    .rect_stroke(gui_element.rect, 4.0, Stroke::new(2.0, color));
+            }
+        }
+    }
+
+    let stroke = Stroke::new(3.0, Color32::RED);
+    for group_index in erc_report.node_groups.iter() {
+        for wire in app.gui_circuit.group_wires(*group_index) {
+            ui.painter()
+                .line_segment([wire.start.to_pos2(), wire.end.to_pos2()], stroke);
+        }
+    }
+}
+
+/// Outlines the largest contributors of the last sensitivity run with their rank
+fn display_sensitivity(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    if app
+        .sensitivity
+        .as_ref()
+        .is_some_and(|x| x.revision != app.history.revision())
+    {
+        app.sensitivity = None;
+    }
+    let Some(report) = &app.sensitivity else {
+        return;
+    };
+
+    let color = Color32::from_rgb(255, 215, 0);
+    let font = FontId::proportional(12.0);
+    let highlighted = report.result.sensitivities.iter().take(report.highlighted);
+    for (rank, sensitivity) in highlighted.enumerate() {
+        let Some(gui_element) = app.gui_circuit.gui_elements.get(&sensitivity.id) else {
+            continue;
+        };
+
+        ui.painter()
+            .rect_stroke(gui_element.rect, 4.0, Stroke::new(2.0, color));
+        let text = match sensitivity.normalized {
+            Some(normalized) => format!("#{} {:+.3}", rank + 1, normalized),
+            None => format!("#{}", rank + 1),
+        };
+        ui.painter().text(
+            gui_element.rect.right_top(),
+            Align2::RIGHT_BOTTOM,
+            text,
+            font.clone(),
+            color,
+        );
     }
 }
 
@@ -175,6 +671,7 @@ fn place_wires(app: &mut SpiceGuIrsApp) {
         return;
     };
 
+    app.history.push(app.gui_circuit.clone());
     app.gui_circuit
         .add_orthogonal_wires(drag_data.start_position, end_position, drag_data.x_first);
 }
@@ -183,26 +680,24 @@ fn place_element(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
     let Some(selected_element) = &app.to_place_element else {
         return;
     };
-    if !ui.ui_contains_pointer() {
+    if !ui.ui_contains_pointer() || app.canvas.panning {
         return;
     }
 
-    let pointer_position = ui
-        .ctx()
-        .pointer_latest_pos()
+    let pointer_position = app
+        .canvas
+        .pointer_position(ui.ctx())
         .unwrap_or_default()
         .to_ipos2(GRID_SIZE)
         .to_pos2();
 
     ui.input(|input| {
         if input.pointer.button_released(PointerButton::Primary) {
-            let size = selected_element
-                .image
-                .calc_size(Vec2::new(128.0, 62.5), selected_element.image.size());
-            let rect = Rect::from_center_size(pointer_position, size);
+            let rect = GuiElement::rect_at(&selected_element.image, pointer_position);
 
+            app.history.push(app.gui_circuit.clone());
             app.gui_circuit.add_element(GuiElement::new(
-                selected_element.element.clone(),
+                selected_element.element,
                 rect,
                 selected_element.image.clone(),
             ));
@@ -211,53 +706,83 @@ fn place_element(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
 }
 
 fn rotate_to_place_element(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+    let Some(to_place_element) = &mut app.to_place_element else {
+        return;
+    };
+
     ctx.input(|input| {
-        if input.key_pressed(Key::R) {
-            if let Some(to_place_element) = &mut app.to_place_element {
-                let current_angle = to_place_element
-                    .image
-                    .image_options()
-                    .rotation
-                    .unwrap_or_default()
-                    .0
-                    .angle();
-                to_place_element.image = to_place_element
-                    .image
-                    .clone()
-                    .rotate(current_angle + f32::consts::FRAC_PI_2, Vec2::splat(0.5));
-            }
+        let rotation = image_rotation(&to_place_element.image);
+        let (flip_x, flip_y) = image_flip(&to_place_element.image);
+        let orientation = if input.key_pressed(Key::R) {
+            Some((rotation + f32::consts::FRAC_PI_2, (flip_x, flip_y)))
+        } else if input.key_pressed(Key::X) {
+            Some((-rotation, (!flip_x, flip_y)))
+        } else if input.key_pressed(Key::Y) {
+            Some((-rotation, (flip_x, !flip_y)))
+        } else {
+            None
+        };
+
+        if let Some((rotation, flip)) = orientation {
+            to_place_element.image = oriented_image(to_place_element.image.clone(), rotation, flip);
         }
     })
 }
 
-fn delete_selected_element(app: &mut SpiceGuIrsApp, ctx: &Context) {
-    ctx.input(|input| {
-        if input.key_pressed(Key::Delete) {
-            if let Some(selected_element_index) = app.selected_element {
-                app.gui_circuit.remove_element(selected_element_index);
-                app.selected_element = None;
-                println!("Delete Element");
-            }
+/// Rotates or mirrors each selected element around its own center, its pins connect to whatever
+/// wires they land on
+fn orient_selected_element(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    if ctx.wants_keyboard_input() || app.to_place_element.is_some() {
+        return;
+    }
+    if app.selected_elements.is_empty() {
+        return;
+    }
+
+    let key = ctx.input(|input| {
+        [Key::R, Key::X, Key::Y]
+            .into_iter()
+            .find(|x| input.key_pressed(*x))
+    });
+    let Some(key) = key else {
+        return;
+    };
+
+    app.history.push(app.gui_circuit.clone());
+    for id in app.selected_elements.iter() {
+        let Some(gui_element) = app.gui_circuit.gui_elements.get_mut(id) else {
+            continue;
+        };
+        match key {
+            Key::R => gui_element.rotate_quarter(),
+            Key::X => gui_element.flip_horizontal(),
+            _ => gui_element.flip_vertical(),
         }
-    })
+    }
 }
 
-fn delete_selected_node(app: &mut SpiceGuIrsApp, ctx: &Context) {
-    ctx.input(|input| {
-        if input.key_pressed(Key::Delete) {
-            if let Some(selected_element_position) = app.selected_node {
-                app.gui_circuit.remove_node(selected_element_position);
-                app.selected_node = None;
-                println!("Delete Node");
-            }
-        }
-    })
+/// Deletes every selected element, wire group and net label as one undo step
+fn delete_selection(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    if ctx.wants_keyboard_input() || !ctx.input(|x| x.key_pressed(Key::Delete)) {
+        return;
+    }
+    if !app.has_selection() {
+        return;
+    }
+
+    app.history.push(app.gui_circuit.clone());
+    app.remove_selection();
 }
 
 fn deselect_element(app: &mut SpiceGuIrsApp, ctx: &Context) {
     ctx.input(|input| {
         if input.pointer.button_released(PointerButton::Secondary) {
             app.to_place_element = None;
+            app.placing_net_label = false;
+            app.placing_junction = false;
         }
     });
 }
@@ -1,39 +1,122 @@
-use egui::{Align2, Context, DragValue, Vec2};
+use egui::{Align2, Context, DragValue, Id, Ui, Vec2};
 
-use crate::app::SpiceGuIrsApp;
+use crate::{
+    app::SpiceGuIrsApp,
+    circuit::{Distribution, ElementType, NetLabel, Tolerance},
+    history::EditTarget,
+};
 
 pub fn handle_selected_object_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
     handle_selected_element_properties(app, ctx);
     handle_selected_wire_properties(app, ctx);
+    handle_selected_net_label_properties(app, ctx);
 }
 
 fn handle_selected_element_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
-    let Some(selected_index) = app.selected_element else {
+    if app.selected_elements.len() > 1 {
+        handle_shared_element_properties(app, ctx);
+        return;
+    }
+    let Some(&selected_index) = app.selected_elements.first() else {
         return;
     };
     let Some(selected_element) = app
         .gui_circuit
         .gui_elements
-        .get_mut(&selected_index)
-        .map(|x| &mut x.element)
+        .get(&selected_index)
+        .map(|x| x.element)
     else {
         return;
     };
 
     let name = format!("{} {}", selected_element.display_name(), "Properties");
-    let Some(element_unit) = selected_element.display_unit_name().to_owned() else {
+    let Some(element_unit) = selected_element.display_unit_name() else {
+        return;
+    };
+    let Some(element_unit_symbol) = selected_element.display_unit_symbol() else {
         return;
     };
-    let Some(element_unit_symbol) = selected_element.display_unit_symbol().to_owned() else {
+    let Some(mut element_value) = selected_element.value() else {
         return;
     };
-    let Some(element_value) = selected_element.value_mut() else {
+
+    let mut open = true;
+
+    let response = egui::Window::new(name)
+        .movable(false)
+        .collapsible(false)
+        .vscroll(false)
+        .resizable(false)
+        .constrain_to(ctx.available_rect())
+        .anchor(Align2::RIGHT_BOTTOM, Vec2::ZERO)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            designator_editor(app, ui, selected_index);
+
+            let response = ui
+                .horizontal(|ui| {
+                    ui.label(element_unit);
+                    let response = ui.add(DragValue::new(&mut element_value).range(0..=i32::MAX));
+                    ui.label(element_unit_symbol);
+
+                    response
+                })
+                .inner;
+            tolerance_editor(app, ui, selected_index);
+
+            response
+        })
+        .and_then(|x| x.inner);
+
+    if let Some(response) = response {
+        if response.changed() {
+            app.history
+                .push_value_edit(app.gui_circuit.clone(), EditTarget::Element(selected_index));
+            if let Some(value) = app
+                .gui_circuit
+                .gui_elements
+                .get_mut(&selected_index)
+                .and_then(|x| x.element.value_mut())
+            {
+                *value = element_value;
+            }
+        }
+
+        if response.drag_stopped() || response.lost_focus() {
+            app.history.finish_value_edit();
+        }
+    }
+
+    if !open {
+        app.selected_elements.clear();
+    }
+}
+
+/// Properties of a multi selection, the value is only editable when every selected element is of
+/// the same type and then sets all of them at once
+fn handle_shared_element_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let elements = app
+        .selected_elements
+        .iter()
+        .filter_map(|x| app.gui_circuit.gui_elements.get(x))
+        .map(|x| x.element)
+        .collect::<Vec<ElementType>>();
+    let Some(first) = elements.first().copied() else {
         return;
     };
+    let same_type = elements
+        .iter()
+        .all(|x| std::mem::discriminant(x) == std::mem::discriminant(&first));
 
+    let name = match same_type {
+        true => format!("{} {}s Properties", elements.len(), first.display_name()),
+        false => format!("{} Elements Selected", elements.len()),
+    };
+    let mut element_value = first.value();
     let mut open = true;
 
-    egui::Window::new(name)
+    let response = egui::Window::new(name)
+        .id(Id::new("shared_element_properties"))
         .movable(false)
         .collapsible(false)
         .vscroll(false)
@@ -42,28 +125,227 @@ fn handle_selected_element_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
         .anchor(Align2::RIGHT_BOTTOM, Vec2::ZERO)
         .open(&mut open)
         .show(ctx, |ui| {
+            if !same_type {
+                ui.label("Select elements of one type to edit their value together");
+                return None;
+            }
+            let (Some(unit), Some(symbol), Some(value)) = (
+                first.display_unit_name(),
+                first.display_unit_symbol(),
+                element_value.as_mut(),
+            ) else {
+                return None;
+            };
+
+            let mixed = elements.iter().any(|x| x.value() != Some(*value));
             ui.horizontal(|ui| {
-                ui.label(element_unit);
-                ui.add(DragValue::new(element_value).range(0..=i32::MAX));
-                ui.label(element_unit_symbol);
+                ui.label(unit);
+                let response = ui.add(DragValue::new(value).range(0..=i32::MAX));
+                ui.label(symbol);
+                if mixed {
+                    ui.weak("(mixed)");
+                }
+
+                Some(response)
             })
+            .inner
+        })
+        .and_then(|x| x.inner)
+        .flatten();
+
+    if let (Some(response), Some(element_value)) = (response, element_value) {
+        if response.changed() {
+            app.history
+                .push_value_edit(app.gui_circuit.clone(), EditTarget::Selection);
+            for id in app.selected_elements.iter() {
+                if let Some(value) = app
+                    .gui_circuit
+                    .gui_elements
+                    .get_mut(id)
+                    .and_then(|x| x.element.value_mut())
+                {
+                    *value = element_value;
+                }
+            }
+        }
+
+        if response.drag_stopped() || response.lost_focus() {
+            app.history.finish_value_edit();
+        }
+    }
+
+    if !open {
+        app.selected_elements.clear();
+    }
+}
+
+/// Tolerance of the element value used by Monte Carlo runs
+fn tolerance_editor(app: &mut SpiceGuIrsApp, ui: &mut Ui, selected_index: u32) {
+    let Some(tolerance) = app
+        .gui_circuit
+        .gui_elements
+        .get(&selected_index)
+        .map(|x| x.tolerance)
+    else {
+        return;
+    };
+
+    let mut percent = tolerance.percent;
+    let mut distribution = tolerance.distribution;
+    let response = ui
+        .horizontal(|ui| {
+            ui.label("Tolerance");
+            let response = ui.add(
+                DragValue::new(&mut percent)
+                    .range(0.0..=100.0)
+                    .speed(0.1)
+                    .suffix(" %"),
+            );
+            egui::ComboBox::from_id_salt(("tolerance_distribution", selected_index))
+                .selected_text(distribution.display_name())
+                .show_ui(ui, |ui| {
+                    for option in [Distribution::Uniform, Distribution::Gaussian] {
+                        ui.selectable_value(&mut distribution, option, option.display_name());
+                    }
+                });
+
+            response
+        })
+        .inner;
+
+    if response.changed() {
+        app.history
+            .push_value_edit(app.gui_circuit.clone(), EditTarget::Element(selected_index));
+    } else if distribution != tolerance.distribution {
+        app.history.push(app.gui_circuit.clone());
+    }
+    if let Some(gui_element) = app.gui_circuit.gui_elements.get_mut(&selected_index) {
+        gui_element.tolerance = Tolerance {
+            percent,
+            distribution,
+        };
+    }
+
+    if response.drag_stopped() || response.lost_focus() {
+        app.history.finish_value_edit();
+    }
+}
+
+/// Text field for the reference designator. The text is kept in egui memory while it is invalid
+/// and only written to the element once it is a valid designator.
+fn designator_editor(app: &mut SpiceGuIrsApp, ui: &mut Ui, selected_index: u32) {
+    let Some(selected_element) = app.gui_circuit.gui_elements.get(&selected_index) else {
+        return;
+    };
+
+    let text_id = Id::new(("designator", selected_index));
+    let mut text = ui
+        .data(|x| x.get_temp::<String>(text_id))
+        .unwrap_or_else(|| selected_element.name.clone());
+
+    let response = ui
+        .horizontal(|ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut text)
+        })
+        .inner;
+
+    let mut edited_element = selected_element.clone();
+    edited_element.name = text.clone();
+    let validation = app
+        .gui_circuit
+        .validate_designator(selected_index, &edited_element);
+
+    if response.changed() && validation.is_ok() && text != selected_element.name {
+        app.history
+            .push_value_edit(app.gui_circuit.clone(), EditTarget::Element(selected_index));
+        if let Some(gui_element) = app.gui_circuit.gui_elements.get_mut(&selected_index) {
+            gui_element.name = text.clone();
+        }
+    }
+
+    if response.lost_focus() {
+        app.history.finish_value_edit();
+        ui.data_mut(|x| x.remove::<String>(text_id));
+    } else {
+        if let Err(error) = validation {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.data_mut(|x| x.insert_temp(text_id, text));
+    }
+}
+
+fn handle_selected_net_label_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let Some(selected_index) = app.selected_net_label else {
+        return;
+    };
+    let Some(net_label) = app.gui_circuit.net_labels.get(selected_index) else {
+        return;
+    };
+    let name = net_label.name.clone();
+
+    let mut open = true;
+
+    egui::Window::new("Net Label")
+        .movable(false)
+        .collapsible(false)
+        .vscroll(false)
+        .resizable(false)
+        .constrain_to(ctx.available_rect())
+        .anchor(Align2::RIGHT_BOTTOM, Vec2::ZERO)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let text_id = Id::new(("net_label_name", selected_index));
+            let mut text = ui
+                .data(|x| x.get_temp::<String>(text_id))
+                .unwrap_or_else(|| name.clone());
+
+            let response = ui
+                .horizontal(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut text)
+                })
+                .inner;
+            let validation = NetLabel::validate_name(&text);
+
+            if response.changed() && validation.is_ok() && text != name {
+                app.history.push_value_edit(
+                    app.gui_circuit.clone(),
+                    EditTarget::NetLabel(selected_index),
+                );
+                app.gui_circuit.net_labels[selected_index].name = text.clone();
+            }
+
+            if response.lost_focus() {
+                app.history.finish_value_edit();
+                ui.data_mut(|x| x.remove::<String>(text_id));
+            } else {
+                if let Err(error) = validation {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.data_mut(|x| x.insert_temp(text_id, text));
+            }
+            ui.label("Labels matching in any case are connected, GND or 0 connects to ground");
         });
 
     if !open {
-        app.selected_element = None;
+        app.selected_net_label = None;
     }
 }
 
 fn handle_selected_wire_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
-    let Some(selected_position) = app.selected_node else {
+    let Some(&segment) = app.selected_wires.first() else {
         return;
     };
-
-    let name = format!("Wire");
+    if !app.selected_elements.is_empty() {
+        return;
+    }
+    let count = app.selected_wires.len();
 
     let mut open = true;
+    let mut delete_net = false;
 
-    egui::Window::new(name)
+    egui::Window::new("Wire")
         .movable(false)
         .collapsible(false)
         .vscroll(false)
@@ -72,12 +354,29 @@ fn handle_selected_wire_properties(app: &mut SpiceGuIrsApp, ctx: &Context) {
         .anchor(Align2::RIGHT_BOTTOM, Vec2::ZERO)
         .open(&mut open)
         .show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.label(selected_position.to_pos2().to_string());
-            })
+            match count {
+                1 => ui.label(format!(
+                    "{} to {}",
+                    segment.start.to_pos2(),
+                    segment.end.to_pos2()
+                )),
+                _ => ui.label(format!("{} wire segments selected", count)),
+            };
+            delete_net = ui
+                .button("Delete Net")
+                .on_hover_text("Delete every wire connected to the selection")
+                .clicked();
         });
 
+    if delete_net {
+        app.history.push(app.gui_circuit.clone());
+        for segment in app.selected_wires.iter() {
+            app.gui_circuit.remove_node(segment.start);
+        }
+        app.selected_wires.clear();
+    }
+
     if !open {
-        app.selected_node = None;
+        app.selected_wires.clear();
     }
 }
@@ -0,0 +1,473 @@
+use std::collections::VecDeque;
+
+use hashbrown::HashMap;
+
+use crate::{
+    app::SpiceGuIrsApp,
+    circuit::{ElementType, GuiCircuit, Netlist, NetlistElement},
+    utils::union_find::UnionFind,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Severity {
+    /// The circuit can still be simulated but probably not as intended
+    Warning,
+    /// The circuit has no unique solution
+    Error,
+}
+
+/// Problem found by the electrical rule check
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub severity: Severity,
+    pub message: String,
+    /// Ids of the offending elements
+    pub elements: Vec<u32>,
+    /// Node numbers of the offending nodes
+    pub nodes: Vec<usize>,
+}
+
+/// Violations of the last check, highlighted on the schematic until the next edit
+pub struct ErcReport {
+    /// History revision the check ran at
+    pub revision: u64,
+    pub violations: Vec<Violation>,
+    /// Node group indexes of every node of the violations
+    pub node_groups: Vec<usize>,
+}
+
+impl Violation {
+    fn new(severity: Severity, message: String, elements: Vec<u32>, nodes: Vec<usize>) -> Self {
+        Self {
+            severity,
+            message,
+            elements,
+            nodes,
+        }
+    }
+}
+
+/// Runs every rule on the schematic
+pub fn check_circuit(gui_circuit: &GuiCircuit) -> Vec<Violation> {
+    let mut violations = unconnected_pins(gui_circuit);
+
+    match gui_circuit.netlist() {
+        Some(netlist) => violations.extend(check_netlist(&netlist)),
+        None => {
+            violations.push(Violation::new(
+                Severity::Error,
+                "Circuit has no ground connected to a wire".to_string(),
+                vec![],
+                vec![],
+            ));
+            let mut elements = gui_circuit.gui_elements.values().collect::<Vec<_>>();
+            elements.sort_by_key(|x| x.id());
+            for gui_element in elements {
+                if let Some(violation) =
+                    zero_resistor(gui_element.id(), &gui_element.name, &gui_element.element)
+                {
+                    violations.push(violation);
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+/// Element pins that are not on any wire
+fn unconnected_pins(gui_circuit: &GuiCircuit) -> Vec<Violation> {
+    let mut elements = gui_circuit.gui_elements.values().collect::<Vec<_>>();
+    elements.sort_by_key(|x| x.id());
+
+    elements
+        .into_iter()
+        .filter(|x| {
+            x.nodes
+                .iter()
+                .any(|pin| gui_circuit.node_group_index(*pin).is_none())
+        })
+        .map(|x| {
+            let message = match x.element {
+                ElementType::Ground => "Ground is not connected to a wire".to_string(),
+                _ => format!(
+                    "{} has an unconnected pin and is left out of the simulation",
+                    x.name
+                ),
+            };
+            Violation::new(Severity::Warning, message, vec![x.id()], vec![])
+        })
+        .collect()
+}
+
+fn zero_resistor(id: u32, name: &str, element: &ElementType) -> Option<Violation> {
+    match element {
+        ElementType::Resistor(resistance) if *resistance <= 0.0 => Some(Violation::new(
+            Severity::Error,
+            format!("{} has a resistance of {} Ω", name, resistance),
+            vec![id],
+            vec![],
+        )),
+        _ => None,
+    }
+}
+
+/// Rules that only need the resolved nodes
+pub fn check_netlist(netlist: &Netlist) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for netlist_element in netlist.elements.iter() {
+        if let Some(violation) = zero_resistor(
+            netlist_element.id,
+            &netlist_element.name,
+            &netlist_element.element,
+        ) {
+            violations.push(violation);
+        }
+    }
+
+    violations.extend(dangling_nodes(netlist));
+    violations.extend(voltage_loops(netlist));
+    violations.extend(dc_isolated_nodes(netlist));
+
+    violations
+}
+
+/// Nodes touched by only one element pin
+fn dangling_nodes(netlist: &Netlist) -> Vec<Violation> {
+    let mut pins: Vec<Vec<&NetlistElement>> = vec![vec![]; netlist.node_count];
+    for netlist_element in netlist.elements.iter() {
+        pins[netlist_element.node1.0].push(netlist_element);
+        pins[netlist_element.node2.0].push(netlist_element);
+    }
+
+    pins.iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, pins)| pins.len() == 1)
+        .map(|(node, pins)| {
+            Violation::new(
+                Severity::Warning,
+                format!(
+                    "Node {} only connects to one pin of {}",
+                    netlist.node_name(node),
+                    pins[0].name
+                ),
+                vec![pins[0].id],
+                vec![node],
+            )
+        })
+        .collect()
+}
+
+/// Loops made only of voltage sources and inductors, which fix the voltage around the loop
+fn voltage_loops(netlist: &Netlist) -> Vec<Violation> {
+    let mut violations = vec![];
+    let mut sets = UnionFind::new(netlist.node_count);
+    // Accepted loop free edges as (neighbor node, element index)
+    let mut edges: Vec<Vec<(usize, usize)>> = vec![vec![]; netlist.node_count];
+
+    for (index, netlist_element) in netlist.elements.iter().enumerate() {
+        if !matches!(
+            netlist_element.element,
+            ElementType::DCVoltageSource(_) | ElementType::Inductor(_)
+        ) {
+            continue;
+        }
+
+        let node1 = netlist_element.node1.0;
+        let node2 = netlist_element.node2.0;
+        if sets.find(node1) != sets.find(node2) {
+            sets.union(node1, node2);
+            edges[node1].push((node2, index));
+            edges[node2].push((node1, index));
+            continue;
+        }
+
+        let mut loop_elements = path_elements(&edges, node1, node2);
+        loop_elements.push(index);
+        let names = loop_elements
+            .iter()
+            .map(|x| netlist.elements[*x].name.as_str())
+            .collect::<Vec<&str>>();
+        let message = match names.len() {
+            1 => format!("{} is shorted", names[0]),
+            _ => format!(
+                "Voltage sources and inductors form a loop: {}",
+                names.join(", ")
+            ),
+        };
+
+        let mut nodes = loop_elements
+            .iter()
+            .flat_map(|x| [netlist.elements[*x].node1.0, netlist.elements[*x].node2.0])
+            .collect::<Vec<usize>>();
+        nodes.sort();
+        nodes.dedup();
+        violations.push(Violation::new(
+            Severity::Error,
+            message,
+            loop_elements
+                .iter()
+                .map(|x| netlist.elements[*x].id)
+                .collect(),
+            nodes,
+        ));
+    }
+
+    violations
+}
+
+/// Elements on the path from `start` to `end` through a forest of edges
+fn path_elements(edges: &[Vec<(usize, usize)>], start: usize, end: usize) -> Vec<usize> {
+    let mut previous: HashMap<usize, (usize, usize)> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        if node == end {
+            break;
+        }
+
+        for (neighbor, element) in edges[node].iter() {
+            if *neighbor != start && !previous.contains_key(neighbor) {
+                previous.insert(*neighbor, (node, *element));
+                queue.push_back(*neighbor);
+            }
+        }
+    }
+
+    let mut elements = vec![];
+    let mut node = end;
+    while let Some((previous_node, element)) = previous.get(&node) {
+        elements.push(*element);
+        node = *previous_node;
+    }
+
+    elements
+}
+
+/// Groups of nodes without a DC path to ground. They are separated from ground only by current
+/// sources and capacitors, so their voltage is undefined or KCL can not hold.
+fn dc_isolated_nodes(netlist: &Netlist) -> Vec<Violation> {
+    let mut sets = UnionFind::new(netlist.node_count);
+    for netlist_element in netlist.elements.iter() {
+        if matches!(
+            netlist_element.element,
+            ElementType::Resistor(_) | ElementType::DCVoltageSource(_) | ElementType::Inductor(_)
+        ) {
+            sets.union(netlist_element.node1.0, netlist_element.node2.0);
+        }
+    }
+
+    let ground = sets.find(0);
+    let mut components: Vec<(usize, Vec<usize>)> = vec![];
+    for node in 1..netlist.node_count {
+        let root = sets.find(node);
+        if root == ground {
+            continue;
+        }
+
+        match components.iter_mut().find(|x| x.0 == root) {
+            Some(component) => component.1.push(node),
+            None => components.push((root, vec![node])),
+        }
+    }
+
+    components
+        .into_iter()
+        .map(|(_, nodes)| {
+            let inside = |node: usize| nodes.contains(&node);
+            let boundary = netlist
+                .elements
+                .iter()
+                .filter(|x| inside(x.node1.0) != inside(x.node2.0))
+                .collect::<Vec<&NetlistElement>>();
+            let node_names = nodes
+                .iter()
+                .map(|x| netlist.node_name(*x))
+                .collect::<Vec<String>>()
+                .join(", ");
+            let boundary_names = boundary
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ");
+
+            let has_current_source = boundary
+                .iter()
+                .any(|x| matches!(x.element, ElementType::DCCurrentSource(_)));
+            let message = if has_current_source {
+                format!(
+                    "Current sources and capacitors {} form a cut-set around nodes {}",
+                    boundary_names, node_names
+                )
+            } else if boundary.is_empty() {
+                format!("Nodes {} have no DC path to ground", node_names)
+            } else {
+                format!(
+                    "Nodes {} have no DC path to ground, only through {}",
+                    node_names, boundary_names
+                )
+            };
+
+            Violation::new(
+                Severity::Error,
+                message,
+                boundary.iter().map(|x| x.id).collect(),
+                nodes.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Checks the schematic, lists the violations in the terminal and keeps them for highlighting.
+/// Returns whether the circuit can be simulated.
+pub fn run_erc(app: &mut SpiceGuIrsApp, report_success: bool) -> bool {
+    let violations = check_circuit(&app.gui_circuit);
+
+    if !violations.is_empty() || report_success {
+        app.terminal_lines.push("".to_string());
+    }
+    if violations.is_empty() && report_success {
+        app.terminal_lines
+            .push("ERC: no problems found".to_string());
+    }
+    for violation in violations.iter() {
+        let severity = match violation.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        app.terminal_lines
+            .push(format!("ERC {}: {}", severity, violation.message));
+    }
+
+    let node_numbers = app
+        .gui_circuit
+        .node_numbers()
+        .map(|x| x.0)
+        .unwrap_or_default();
+    let node_groups = node_numbers
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| violations.iter().any(|x| x.nodes.contains(node)))
+        .map(|(group, _)| group)
+        .collect();
+
+    let can_simulate = violations.iter().all(|x| x.severity != Severity::Error);
+    app.erc_report = Some(ErcReport {
+        revision: app.history.revision(),
+        violations,
+        node_groups,
+    });
+
+    can_simulate
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::{element, numbered_netlist},
+        circuit::ElementType,
+    };
+
+    use super::{check_netlist, Severity};
+
+    #[test]
+    fn valid_circuit() {
+        let netlist = numbered_netlist(
+            3,
+            vec![
+                element("V1", ElementType::DCVoltageSource(5.0), 1, 0),
+                element("R1", ElementType::Resistor(1000.0), 1, 2),
+                element("C1", ElementType::Capacitor(1e-6), 2, 0),
+                element("R2", ElementType::Resistor(1000.0), 2, 0),
+            ],
+        );
+
+        assert!(check_netlist(&netlist).is_empty());
+    }
+
+    #[test]
+    fn zero_resistor() {
+        let netlist = numbered_netlist(
+            2,
+            vec![
+                element("V1", ElementType::DCVoltageSource(5.0), 1, 0),
+                element("R1", ElementType::Resistor(0.0), 1, 0),
+            ],
+        );
+
+        let violations = check_netlist(&netlist);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].elements, [1]);
+    }
+
+    #[test]
+    fn voltage_source_loop() {
+        let netlist = numbered_netlist(
+            3,
+            vec![
+                element("V1", ElementType::DCVoltageSource(5.0), 1, 0),
+                element("L1", ElementType::Inductor(1e-3), 1, 2),
+                element("V2", ElementType::DCVoltageSource(3.0), 2, 0),
+                element("R1", ElementType::Resistor(100.0), 2, 0),
+            ],
+        );
+
+        let violations = check_netlist(&netlist);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Error);
+        let mut elements = violations[0].elements.clone();
+        elements.sort();
+        assert_eq!(elements, [0, 1, 2]);
+    }
+
+    #[test]
+    fn capacitor_floating_node() {
+        let netlist = numbered_netlist(
+            3,
+            vec![
+                element("V1", ElementType::DCVoltageSource(5.0), 1, 0),
+                element("C1", ElementType::Capacitor(1e-6), 1, 2),
+                element("C2", ElementType::Capacitor(1e-6), 2, 0),
+            ],
+        );
+
+        let violations = check_netlist(&netlist);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].nodes, [2]);
+        assert!(violations[0].message.contains("no DC path"));
+    }
+
+    #[test]
+    fn current_source_cut_set() {
+        let netlist = numbered_netlist(
+            3,
+            vec![
+                element("I1", ElementType::DCCurrentSource(1e-3), 0, 1),
+                element("R1", ElementType::Resistor(100.0), 1, 2),
+                element("C1", ElementType::Capacitor(1e-6), 2, 0),
+            ],
+        );
+
+        let violations = check_netlist(&netlist);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].nodes, [1, 2]);
+        assert!(violations[0].message.contains("cut-set"));
+    }
+
+    #[test]
+    fn dangling_pin() {
+        let netlist = numbered_netlist(
+            3,
+            vec![
+                element("V1", ElementType::DCVoltageSource(5.0), 1, 0),
+                element("R1", ElementType::Resistor(100.0), 1, 2),
+            ],
+        );
+
+        let violations = check_netlist(&netlist);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Warning);
+        assert_eq!(violations[0].nodes, [2]);
+    }
+}
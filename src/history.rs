@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use crate::circuit::GuiCircuit;
+
+/// Amount of undo steps kept before the oldest ones are dropped
+const MAX_HISTORY: usize = 100;
+
+/// Object whose value is being edited
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditTarget {
+    Element(u32),
+    /// Shared value of all selected elements
+    Selection,
+    NetLabel(usize),
+}
+
+/// Snapshot based undo/redo history of the schematic
+#[derive(Default)]
+pub struct History {
+    undo_stack: VecDeque<GuiCircuit>,
+    redo_stack: Vec<GuiCircuit>,
+    /// Object whose value is being edited, so one drag only records one step
+    editing: Option<EditTarget>,
+    /// Incremented on every recorded change, so results derived from the circuit can tell they
+    /// are stale
+    revision: u64,
+}
+
+impl History {
+    /// Records the circuit as it was before a mutation
+    pub fn push(&mut self, snapshot: GuiCircuit) {
+        self.revision += 1;
+        self.editing = None;
+        self.redo_stack.clear();
+        self.undo_stack.push_back(snapshot);
+
+        if self.undo_stack.len() > MAX_HISTORY {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Records the circuit before a value edit of `target`, unless the edit is still ongoing
+    pub fn push_value_edit(&mut self, snapshot: GuiCircuit, target: EditTarget) {
+        if self.editing == Some(target) {
+            self.revision += 1;
+            return;
+        }
+
+        self.push(snapshot);
+        self.editing = Some(target);
+    }
+
+    pub fn finish_value_edit(&mut self) {
+        self.editing = None;
+    }
+
+    pub fn undo(&mut self, gui_circuit: &mut GuiCircuit) -> bool {
+        let Some(snapshot) = self.undo_stack.pop_back() else {
+            return false;
+        };
+
+        self.revision += 1;
+        self.editing = None;
+        self.redo_stack
+            .push(std::mem::replace(gui_circuit, snapshot));
+
+        true
+    }
+
+    pub fn redo(&mut self, gui_circuit: &mut GuiCircuit) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return false;
+        };
+
+        self.revision += 1;
+        self.editing = None;
+        self.undo_stack
+            .push_back(std::mem::replace(gui_circuit, snapshot));
+
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Drops all steps, used when the circuit is replaced
+    pub fn clear(&mut self) {
+        *self = Self {
+            revision: self.revision + 1,
+            ..Self::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Pos2;
+
+    use crate::circuit::{add_test_element, ElementType, GuiCircuit};
+
+    use super::{EditTarget, History, MAX_HISTORY};
+
+    const RESISTOR: ElementType = ElementType::Resistor(10.0);
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::default();
+        let mut circuit = GuiCircuit::default();
+
+        history.push(circuit.clone());
+        add_test_element(&mut circuit, RESISTOR, Pos2::new(64.0, 64.0), 0.0);
+        history.push(circuit.clone());
+        add_test_element(&mut circuit, RESISTOR, Pos2::new(64.0, 64.0), 0.0);
+        assert_eq!(circuit.gui_elements.len(), 2);
+
+        assert!(history.undo(&mut circuit));
+        assert_eq!(circuit.gui_elements.len(), 1);
+        assert!(history.undo(&mut circuit));
+        assert_eq!(circuit.gui_elements.len(), 0);
+        assert!(!history.undo(&mut circuit));
+
+        assert!(history.redo(&mut circuit));
+        assert!(history.redo(&mut circuit));
+        assert_eq!(circuit.gui_elements.len(), 2);
+        assert!(!history.redo(&mut circuit));
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut history = History::default();
+        let mut circuit = GuiCircuit::default();
+
+        history.push(circuit.clone());
+        add_test_element(&mut circuit, RESISTOR, Pos2::new(64.0, 64.0), 0.0);
+        history.undo(&mut circuit);
+        assert!(history.can_redo());
+
+        history.push(circuit.clone());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn value_edits_are_merged() {
+        let mut history = History::default();
+        let circuit = GuiCircuit::default();
+
+        history.push_value_edit(circuit.clone(), EditTarget::Element(0));
+        history.push_value_edit(circuit.clone(), EditTarget::Element(0));
+        assert_eq!(history.undo_stack.len(), 1);
+
+        history.finish_value_edit();
+        history.push_value_edit(circuit.clone(), EditTarget::Element(0));
+        history.push_value_edit(circuit.clone(), EditTarget::Element(1));
+        assert_eq!(history.undo_stack.len(), 3);
+    }
+
+    #[test]
+    fn revision_changes_on_every_edit() {
+        let mut history = History::default();
+        let mut circuit = GuiCircuit::default();
+        let mut revisions = vec![history.revision()];
+
+        history.push_value_edit(circuit.clone(), EditTarget::Element(0));
+        revisions.push(history.revision());
+        history.push_value_edit(circuit.clone(), EditTarget::Element(0));
+        revisions.push(history.revision());
+        history.undo(&mut circuit);
+        revisions.push(history.revision());
+        history.clear();
+        revisions.push(history.revision());
+
+        assert!(revisions.windows(2).all(|x| x[0] < x[1]));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = History::default();
+        for _ in 0..MAX_HISTORY + 10 {
+            history.push(GuiCircuit::default());
+        }
+
+        assert_eq!(history.undo_stack.len(), MAX_HISTORY);
+    }
+}
@@ -1,10 +1,23 @@
 use app::SpiceGuIrsApp;
 
+mod analysis;
 mod app;
+mod canvas;
 mod circuit;
+mod clipboard;
 mod element_pointer;
 mod element_properties;
+mod erc;
+mod history;
 mod menu_bar;
+mod monte_carlo_window;
+mod operating_point;
+mod plot_panel;
+mod pole_zero_window;
+mod runner_windows;
+mod schematic_file;
+mod spice_export;
+mod spice_import;
 mod terminal;
 pub mod utils;
 
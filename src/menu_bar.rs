@@ -1,53 +1,122 @@
-use egui::{Image, ImageButton, Ui};
+use egui::{Button, Image, ImageButton, Key, KeyboardShortcut, Modifiers, Ui};
 use spice_rs::runners::dc_op::dc_op;
 
 use crate::{
+    analysis::unknown_names,
     app::SpiceGuIrsApp,
-    circuit::{ElementType, ToPlaceElement},
+    canvas::{fit_to_content, zoom_to_selection},
+    circuit::{ElementType, GuiCircuit, ToPlaceElement},
+    clipboard::{copy_selection, cut_selection, paste},
+    erc::run_erc,
+    operating_point::OperatingPoint,
+    runner_windows::RunnerWindow,
+    schematic_file::{load_schematic, save_schematic, SCHEMATIC_EXTENSION},
+    spice_export::{save_spice_netlist, SPICE_EXTENSION},
+    spice_import::load_spice_netlist,
     CAPACITOR_SOURCE, DC_CURRENT_SOURCE, DC_VOLTAGE_SOURCE, GROUND_SOURCE, INDUCTOR_SOURCE,
     RESISTOR_SOURCE,
 };
 
+const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+// Only shown in the menu, the keys themselves arrive as egui copy, cut and paste events
+const CUT_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::X);
+const COPY_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::C);
+const PASTE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::V);
+
 pub fn menu_bar(app: &mut SpiceGuIrsApp, ctx: &egui::Context) {
+    history_shortcuts(app, ctx);
+
     egui::TopBottomPanel::top("menu_bar")
         .default_height(50.0)
         .min_height(0.0)
         .show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
-                if ui.button("File").clicked() {}
+                ui.menu_button("File", |ui| {
+                    file_menu(app, ui);
+                });
+
+                ui.menu_button("Edit", |ui| {
+                    edit_menu(app, ui);
+                });
+
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut app.plot.open, "Plot");
+                    ui.checkbox(&mut app.show_operating_point, "Operating Point Values");
+
+                    ui.separator();
+
+                    let fit_button = Button::new("Fit to Content").shortcut_text("Home");
+                    if ui.add(fit_button).clicked() {
+                        fit_to_content(app);
+                        ui.close_menu();
+                    }
+
+                    if ui
+                        .add_enabled(app.has_selection(), Button::new("Zoom to Selection"))
+                        .clicked()
+                    {
+                        zoom_to_selection(app);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Reset Zoom").clicked() {
+                        app.canvas.reset();
+                        ui.close_menu();
+                    }
+                });
 
                 ui.menu_button("Runners", |ui| {
+                    if ui.button("Electrical Rule Check").clicked() {
+                        run_erc(app, true);
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
                     if ui.button("DC Operating Point").clicked() {
-                        if let Some(circuit) = app.gui_circuit.construct_circuit() {
-                            match dc_op(&circuit) {
-                                Ok(values) => {
-                                    app.terminal_lines.push("".to_string());
-                                    for (i, value) in values.iter().enumerate() {
-                                        let unit = if i < circuit.node_count().saturating_sub(1) {
-                                            "V"
-                                        } else {
-                                            "A"
-                                        };
-
-                                        let name = if i < circuit.node_count().saturating_sub(1) {
-                                            format!("V{}", i + 1)
-                                        } else {
-                                            format!(
-                                                "I{}",
-                                                i - circuit.node_count().saturating_sub(2)
-                                            )
-                                        };
-
-                                        app.terminal_lines
-                                            .push(format!("{}: {}{}", name, value, unit));
-                                    }
-                                }
-                                Err(error) => app
-                                    .terminal_lines
-                                    .push(format!("Error: {}", error.to_string())),
-                            }
-                        }
+                        dc_operating_point(app);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Transfer Function...").clicked() {
+                        app.runner_window = Some(RunnerWindow::TransferFunction);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("DC Sweep...").clicked() {
+                        app.runner_window = Some(RunnerWindow::DcSweep);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("DC Sensitivity...").clicked() {
+                        app.runner_window = Some(RunnerWindow::Sensitivity);
+                        ui.close_menu();
+                    }
 
+                    if ui.button("Transient...").clicked() {
+                        app.runner_window = Some(RunnerWindow::Transient);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("AC Sweep...").clicked() {
+                        app.runner_window = Some(RunnerWindow::Ac);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Pole-Zero...").clicked() {
+                        app.runner_window = Some(RunnerWindow::PoleZero);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Parametric Sweep...").clicked() {
+                        app.runner_window = Some(RunnerWindow::Parametric);
+                        ui.close_menu();
+                    }
+
+                    if ui.button("Monte Carlo...").clicked() {
+                        app.runner_window = Some(RunnerWindow::MonteCarlo);
                         ui.close_menu();
                     }
                 });
@@ -63,6 +132,248 @@ pub fn menu_bar(app: &mut SpiceGuIrsApp, ctx: &egui::Context) {
         });
 }
 
+fn file_menu(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    if ui.button("New").clicked() {
+        app.gui_circuit = GuiCircuit::default();
+        app.clear_selection();
+        app.schematic_path = None;
+        app.history.clear();
+
+        ui.close_menu();
+    }
+
+    if ui.button("Open...").clicked() {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Schematic", &[SCHEMATIC_EXTENSION])
+            .pick_file()
+        {
+            match load_schematic(&path) {
+                Ok(gui_circuit) => {
+                    app.gui_circuit = gui_circuit;
+                    app.clear_selection();
+                    app.history.clear();
+                    app.terminal_lines
+                        .push(format!("Opened {}", path.display()));
+                    app.schematic_path = Some(path);
+                }
+                Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+            }
+        }
+
+        ui.close_menu();
+    }
+
+    if ui.button("Save").clicked() {
+        match app.schematic_path.clone() {
+            Some(path) => save_to_path(app, path),
+            None => save_as(app),
+        }
+
+        ui.close_menu();
+    }
+
+    if ui.button("Save As...").clicked() {
+        save_as(app);
+
+        ui.close_menu();
+    }
+
+    ui.separator();
+
+    if ui.button("Import SPICE Netlist...").clicked() {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("SPICE Netlist", &[SPICE_EXTENSION, "net", "sp", "spice"])
+            .pick_file()
+        {
+            match load_spice_netlist(&path) {
+                Ok(imported) => {
+                    app.gui_circuit = imported.gui_circuit;
+                    app.clear_selection();
+                    app.schematic_path = None;
+                    app.history.clear();
+                    app.terminal_lines.extend(imported.messages);
+                }
+                Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+            }
+        }
+
+        ui.close_menu();
+    }
+
+    if ui.button("Export SPICE Netlist...").clicked() {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("SPICE Netlist", &[SPICE_EXTENSION])
+            .set_file_name(format!("circuit.{}", SPICE_EXTENSION))
+            .save_file()
+        {
+            match save_spice_netlist(&app.gui_circuit, &path) {
+                Ok(omitted) => {
+                    app.terminal_lines
+                        .push(format!("Exported {}", path.display()));
+                    if !omitted.is_empty() {
+                        app.terminal_lines.push(format!(
+                            "Warning: left out {}, a pin is not on a wire",
+                            omitted.join(", ")
+                        ));
+                    }
+                }
+                Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+            }
+        }
+
+        ui.close_menu();
+    }
+
+    if ui
+        .add_enabled(
+            app.simulation_result.is_some(),
+            Button::new("Export Results as CSV..."),
+        )
+        .clicked()
+    {
+        if let (Some(result), Some(path)) = (
+            app.simulation_result.as_ref(),
+            rfd::FileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .set_file_name("results.csv")
+                .save_file(),
+        ) {
+            match std::fs::write(&path, result.to_csv()) {
+                Ok(()) => app
+                    .terminal_lines
+                    .push(format!("Exported {}", path.display())),
+                Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+            }
+        }
+
+        ui.close_menu();
+    }
+}
+
+fn edit_menu(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let undo_button = Button::new("Undo").shortcut_text(ui.ctx().format_shortcut(&UNDO_SHORTCUT));
+    if ui
+        .add_enabled(app.history.can_undo(), undo_button)
+        .clicked()
+    {
+        undo(app);
+
+        ui.close_menu();
+    }
+
+    let redo_button = Button::new("Redo").shortcut_text(ui.ctx().format_shortcut(&REDO_SHORTCUT));
+    if ui
+        .add_enabled(app.history.can_redo(), redo_button)
+        .clicked()
+    {
+        redo(app);
+
+        ui.close_menu();
+    }
+
+    ui.separator();
+
+    let has_selection = !app.selected_elements.is_empty() || !app.selected_wires.is_empty();
+    let cut_button = Button::new("Cut").shortcut_text(ui.ctx().format_shortcut(&CUT_SHORTCUT));
+    if ui.add_enabled(has_selection, cut_button).clicked() {
+        cut_selection(app, ui.ctx());
+
+        ui.close_menu();
+    }
+
+    let copy_button = Button::new("Copy").shortcut_text(ui.ctx().format_shortcut(&COPY_SHORTCUT));
+    if ui.add_enabled(has_selection, copy_button).clicked() {
+        copy_selection(app, ui.ctx());
+
+        ui.close_menu();
+    }
+
+    let paste_button =
+        Button::new("Paste").shortcut_text(ui.ctx().format_shortcut(&PASTE_SHORTCUT));
+    if ui
+        .add_enabled(app.clipboard.is_some(), paste_button)
+        .clicked()
+    {
+        if let Some(text) = app.clipboard.clone() {
+            paste(app, &text, None);
+        }
+
+        ui.close_menu();
+    }
+}
+
+fn history_shortcuts(app: &mut SpiceGuIrsApp, ctx: &egui::Context) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    // Redo first, the undo shortcut would also match with shift held
+    if ctx.input_mut(|input| input.consume_shortcut(&REDO_SHORTCUT)) {
+        redo(app);
+    } else if ctx.input_mut(|input| input.consume_shortcut(&UNDO_SHORTCUT)) {
+        undo(app);
+    }
+}
+
+fn undo(app: &mut SpiceGuIrsApp) {
+    if app.history.undo(&mut app.gui_circuit) {
+        app.validate_selection();
+    }
+}
+
+fn redo(app: &mut SpiceGuIrsApp) {
+    if app.history.redo(&mut app.gui_circuit) {
+        app.validate_selection();
+    }
+}
+
+fn dc_operating_point(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+    let (Some(circuit), Some(netlist)) = (
+        app.gui_circuit.construct_circuit(),
+        app.gui_circuit.netlist(),
+    ) else {
+        return;
+    };
+
+    match dc_op(&circuit) {
+        Ok(values) => {
+            app.terminal_lines.push("".to_string());
+            for ((name, unit), value) in unknown_names(&netlist).iter().zip(values.iter()) {
+                app.terminal_lines
+                    .push(format!("{}: {}{}", name, value, unit));
+            }
+
+            let values = values.iter().map(|x| *x as f64).collect::<Vec<f64>>();
+            app.operating_point =
+                OperatingPoint::new(&app.gui_circuit, &values, app.history.revision());
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+fn save_as(app: &mut SpiceGuIrsApp) {
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("Schematic", &[SCHEMATIC_EXTENSION])
+        .set_file_name(format!("schematic.{}", SCHEMATIC_EXTENSION))
+        .save_file()
+    {
+        save_to_path(app, path);
+    }
+}
+
+fn save_to_path(app: &mut SpiceGuIrsApp, path: std::path::PathBuf) {
+    match save_schematic(&app.gui_circuit, &path) {
+        Ok(()) => {
+            app.terminal_lines.push(format!("Saved {}", path.display()));
+            app.schematic_path = Some(path);
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
 fn element_bar(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
     if ui
         .add(ImageButton::new(GROUND_SOURCE).rounding(5.0))
@@ -132,4 +443,23 @@ fn element_bar(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
             element_image,
         ));
     }
+
+    if ui.add(Button::new("Net Label").rounding(5.0)).clicked() {
+        app.to_place_element = None;
+        app.placing_net_label = true;
+        app.placing_junction = false;
+    }
+    if ui
+        .add(Button::new("Junction").rounding(5.0))
+        .on_hover_text("Click wire crossings to connect or separate them")
+        .clicked()
+    {
+        app.to_place_element = None;
+        app.placing_net_label = false;
+        app.placing_junction = true;
+    }
+    if app.to_place_element.is_some() {
+        app.placing_net_label = false;
+        app.placing_junction = false;
+    }
 }
@@ -0,0 +1,138 @@
+use egui::{Align, Context, Layout, Rect, Sense, Ui, Vec2};
+
+use crate::{
+    analysis::monte_carlo::{histogram, MonteCarloResult, Statistics},
+    app::SpiceGuIrsApp,
+    utils::si::format_si,
+};
+
+const HISTOGRAM_HEIGHT: f32 = 80.0;
+
+/// Result of the last Monte Carlo run and which of its values are shown
+pub struct MonteCarloView {
+    pub result: MonteCarloResult,
+    /// Whether statistics and a histogram are shown, by unknown index
+    pub shown: Vec<bool>,
+    pub bins: usize,
+    pub open: bool,
+}
+
+impl MonteCarloView {
+    /// Shows the node voltages to begin with
+    pub fn new(result: MonteCarloResult) -> Self {
+        let shown = result.names.iter().map(|(_, unit)| *unit == "V").collect();
+        Self {
+            result,
+            shown,
+            bins: 20,
+            open: true,
+        }
+    }
+}
+
+pub fn handle_monte_carlo_window(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let Some(view) = app.monte_carlo.as_mut() else {
+        return;
+    };
+    if !view.open {
+        return;
+    }
+
+    let mut open = true;
+    egui::Window::new("Monte Carlo")
+        .default_size(Vec2::new(420.0, 360.0))
+        .open(&mut open)
+        .show(ctx, |ui| monte_carlo_contents(ui, view));
+    view.open &= open;
+}
+
+fn monte_carlo_contents(ui: &mut Ui, view: &mut MonteCarloView) {
+    ui.label(&view.result.title);
+    ui.horizontal_wrapped(|ui| {
+        for ((name, _), shown) in view.result.names.iter().zip(view.shown.iter_mut()) {
+            ui.checkbox(shown, name);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Bins");
+        ui.add(egui::DragValue::new(&mut view.bins).range(1..=200));
+    });
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("monte_carlo_statistics")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                for heading in ["", "Min", "Max", "Mean", "σ"] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                for (index, (name, unit)) in view.result.names.iter().enumerate() {
+                    let Some(statistics) = view.result.statistics(index) else {
+                        continue;
+                    };
+                    if !view.shown[index] {
+                        continue;
+                    }
+
+                    ui.label(name);
+                    for value in [
+                        statistics.min,
+                        statistics.max,
+                        statistics.mean,
+                        statistics.std_dev,
+                    ] {
+                        ui.monospace(format_si(value, unit));
+                    }
+                    ui.end_row();
+                }
+            });
+
+        for (index, (name, unit)) in view.result.names.iter().enumerate() {
+            if !view.shown[index] {
+                continue;
+            }
+            ui.separator();
+            ui.label(name);
+            histogram_plot(ui, &view.result.samples[index], view.bins, unit);
+        }
+    });
+}
+
+/// Bars of the value counts with the value range written below
+fn histogram_plot(ui: &mut Ui, values: &[f64], bins: usize, unit: &str) {
+    let counts = histogram(values, bins);
+    let size = Vec2::new(ui.available_width(), HISTOGRAM_HEIGHT);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let highest = counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar_width = rect.width() / counts.len().max(1) as f32;
+    for (bin, count) in counts.iter().enumerate() {
+        let height = rect.height() * *count as f32 / highest as f32;
+        let left = rect.left() + bar_width * bin as f32;
+        let bar = Rect::from_min_max(
+            egui::pos2(left, rect.bottom() - height),
+            egui::pos2(left + bar_width, rect.bottom()),
+        );
+        painter.rect_filled(
+            bar.shrink2(Vec2::new(0.5, 0.0)),
+            0.0,
+            visuals.selection.bg_fill,
+        );
+    }
+
+    let Some(statistics) = Statistics::new(values) else {
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.monospace(format_si(statistics.min, unit));
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            ui.monospace(format_si(statistics.max, unit));
+        });
+    });
+}
@@ -0,0 +1,108 @@
+use hashbrown::HashMap;
+
+use crate::{analysis::unknown_names, circuit::GuiCircuit};
+
+/// DC operating point values mapped back onto the node groups and elements of the schematic
+pub struct OperatingPoint {
+    /// History revision the values belong to, any later edit makes them stale
+    pub revision: u64,
+    /// Name and voltage of every node group, `None` for grounded groups
+    pub node_voltages: Vec<Option<(String, f64)>>,
+    /// Name and branch current of voltage sources and inductors by element id
+    pub branch_currents: HashMap<u32, (String, f64)>,
+}
+
+impl OperatingPoint {
+    /// Maps the DC operating point `values`, node voltages followed by branch currents
+    pub fn new(gui_circuit: &GuiCircuit, values: &[f64], revision: u64) -> Option<Self> {
+        let netlist = gui_circuit.netlist()?;
+        let (node_numbers, node_count) = gui_circuit.node_numbers()?;
+        let names = unknown_names(&netlist);
+        let named_value = |index: usize| Some((names.get(index)?.0.clone(), *values.get(index)?));
+
+        let node_voltages = node_numbers
+            .iter()
+            .map(|node| match node {
+                0 => Some(None),
+                node => named_value(node - 1).map(Some),
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let branch_currents = netlist
+            .elements
+            .iter()
+            .zip(netlist.stamps())
+            .filter_map(|(netlist_element, stamp)| Some((netlist_element.id, stamp?.branch()?)))
+            .map(|(id, branch)| named_value(node_count - 1 + branch).map(|x| (id, x)))
+            .collect::<Option<HashMap<_, _>>>()?;
+
+        Some(Self {
+            revision,
+            node_voltages,
+            branch_currents,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Pos2;
+
+    use crate::{
+        circuit::{add_test_element, ElementType, GuiCircuit},
+        utils::ipos2::IPos2,
+    };
+
+    use super::OperatingPoint;
+
+    #[test]
+    fn values_are_mapped_to_groups_and_elements() {
+        let mut circuit = GuiCircuit::default();
+        add_test_element(
+            &mut circuit,
+            ElementType::DCVoltageSource(5.0),
+            Pos2::new(64.0, 64.0),
+            0.0,
+        );
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(1000.0),
+            Pos2::new(160.0, 128.0),
+            std::f32::consts::FRAC_PI_2,
+        );
+        add_test_element(
+            &mut circuit,
+            ElementType::Ground,
+            Pos2::new(32.0, 160.0),
+            0.0,
+        );
+        circuit.add_orthogonal_wires(IPos2::new(96, 64), IPos2::new(160, 96), true);
+        circuit.add_orthogonal_wires(IPos2::new(32, 64), IPos2::new(32, 144), true);
+        circuit.add_orthogonal_wires(IPos2::new(160, 160), IPos2::new(32, 144), false);
+
+        let operating_point = OperatingPoint::new(&circuit, &[5.0, -0.005], 3).unwrap();
+        assert_eq!(operating_point.revision, 3);
+
+        let top_group = circuit.node_group_index(IPos2::new(96, 64)).unwrap();
+        let ground_group = circuit.node_group_index(IPos2::new(32, 144)).unwrap();
+        assert_eq!(
+            operating_point.node_voltages[top_group],
+            Some(("V1".to_string(), 5.0))
+        );
+        assert_eq!(operating_point.node_voltages[ground_group], None);
+
+        let source_id = circuit
+            .gui_elements
+            .values()
+            .find(|x| matches!(x.element, ElementType::DCVoltageSource(_)))
+            .unwrap()
+            .id();
+        assert_eq!(
+            operating_point.branch_currents[&source_id],
+            ("I(V1)".to_string(), -0.005)
+        );
+        assert_eq!(operating_point.branch_currents.len(), 1);
+
+        assert!(OperatingPoint::new(&circuit, &[5.0], 3).is_none());
+    }
+}
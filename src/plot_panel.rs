@@ -0,0 +1,637 @@
+use egui::{
+    Align2, Color32, Context, FontId, PointerButton, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2,
+};
+use hashbrown::HashSet;
+
+use crate::{
+    analysis::{SimulationResult, Trace},
+    app::SpiceGuIrsApp,
+    utils::si::format_si,
+};
+
+const TRACE_COLORS: [Color32; 8] = [
+    Color32::from_rgb(255, 215, 0),
+    Color32::from_rgb(0, 200, 255),
+    Color32::from_rgb(255, 90, 90),
+    Color32::from_rgb(120, 230, 120),
+    Color32::from_rgb(230, 120, 255),
+    Color32::from_rgb(255, 160, 60),
+    Color32::from_rgb(160, 160, 255),
+    Color32::from_rgb(200, 200, 200),
+];
+const CURSOR_COLORS: [Color32; 2] = [
+    Color32::from_rgb(255, 255, 255),
+    Color32::from_rgb(255, 120, 200),
+];
+const CURSOR_NAMES: [&str; 2] = ["A", "B"];
+/// Space left of and below the plot for the tick labels
+const Y_AXIS_WIDTH: f32 = 72.0;
+const X_AXIS_HEIGHT: f32 = 20.0;
+const MIN_TICK_SPACING: f32 = 64.0;
+const READOUT_ROW_HEIGHT: f32 = 18.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PlotDock {
+    Right,
+    Bottom,
+    Window,
+}
+
+impl PlotDock {
+    fn display_name(&self) -> &'static str {
+        match self {
+            PlotDock::Right => "Right",
+            PlotDock::Bottom => "Bottom",
+            PlotDock::Window => "Window",
+        }
+    }
+}
+
+/// Visible area of the plot in sweep and trace units
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PlotBounds {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl PlotBounds {
+    fn width(&self) -> f64 {
+        self.x_max - self.x_min
+    }
+
+    fn height(&self) -> f64 {
+        self.y_max - self.y_min
+    }
+
+    fn screen_position(&self, rect: Rect, x: f64, y: f64) -> Pos2 {
+        Pos2::new(
+            rect.left() + ((x - self.x_min) / self.width()) as f32 * rect.width(),
+            rect.bottom() - ((y - self.y_min) / self.height()) as f32 * rect.height(),
+        )
+    }
+
+    fn plot_position(&self, rect: Rect, position: Pos2) -> (f64, f64) {
+        (
+            self.x_min + ((position.x - rect.left()) / rect.width()) as f64 * self.width(),
+            self.y_min + ((rect.bottom() - position.y) / rect.height()) as f64 * self.height(),
+        )
+    }
+
+    fn translate(&mut self, x: f64, y: f64) {
+        self.x_min += x;
+        self.x_max += x;
+        self.y_min += y;
+        self.y_max += y;
+    }
+
+    /// Scales the bounds around `center`, factors above one zoom in
+    fn zoom(&mut self, center: (f64, f64), x_factor: f64, y_factor: f64) {
+        self.x_min = center.0 + (self.x_min - center.0) / x_factor;
+        self.x_max = center.0 + (self.x_max - center.0) / x_factor;
+        self.y_min = center.1 + (self.y_min - center.1) / y_factor;
+        self.y_max = center.1 + (self.y_max - center.1) / y_factor;
+    }
+}
+
+pub struct PlotState {
+    pub open: bool,
+    pub dock: PlotDock,
+    /// Names of the shown traces, kept by name so reruns show the same traces
+    pub visible_traces: HashSet<String>,
+    /// Manually zoomed or panned bounds, `None` autoscales to the visible traces
+    pub bounds: Option<PlotBounds>,
+    pub cursors: [Option<f64>; 2],
+}
+
+impl Default for PlotState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            dock: PlotDock::Right,
+            visible_traces: HashSet::new(),
+            bounds: None,
+            cursors: [None; 2],
+        }
+    }
+}
+
+impl PlotState {
+    /// Prepares the plot for a new result, showing the node voltages or their AC magnitudes if
+    /// none of the previously shown traces exist in it
+    pub fn show_result(&mut self, result: &SimulationResult) {
+        self.visible_traces
+            .retain(|name| result.traces.iter().any(|x| &x.name == name));
+        if self.visible_traces.is_empty() {
+            self.visible_traces = result
+                .traces
+                .iter()
+                .filter(|x| x.unit == "V" || x.name.starts_with("dB(V"))
+                .map(|x| x.name.clone())
+                .collect();
+        }
+
+        self.open = true;
+        self.bounds = None;
+        self.cursors = [None; 2];
+    }
+}
+
+pub fn handle_plot_panel(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    if !app.plot.open {
+        return;
+    }
+
+    let plot = &mut app.plot;
+    let result = app.simulation_result.as_ref();
+    match plot.dock {
+        PlotDock::Right => {
+            egui::SidePanel::right("plot")
+                .resizable(true)
+                .default_width(420.0)
+                .show(ctx, |ui| plot_contents(ui, plot, result));
+        }
+        PlotDock::Bottom => {
+            egui::TopBottomPanel::bottom("plot")
+                .resizable(true)
+                .default_height(260.0)
+                .show(ctx, |ui| plot_contents(ui, plot, result));
+        }
+        PlotDock::Window => {
+            let mut open = true;
+            egui::Window::new("Plot")
+                .default_size(Vec2::new(480.0, 320.0))
+                .open(&mut open)
+                .show(ctx, |ui| plot_contents(ui, plot, result));
+            plot.open &= open;
+        }
+    }
+}
+
+fn plot_contents(ui: &mut Ui, plot: &mut PlotState, result: Option<&SimulationResult>) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("plot_dock")
+            .selected_text(plot.dock.display_name())
+            .show_ui(ui, |ui| {
+                for dock in [PlotDock::Right, PlotDock::Bottom, PlotDock::Window] {
+                    ui.selectable_value(&mut plot.dock, dock, dock.display_name());
+                }
+            });
+        if ui.button("Autoscale").clicked() {
+            plot.bounds = None;
+        }
+        if ui
+            .add_enabled(
+                plot.cursors.iter().any(|x| x.is_some()),
+                egui::Button::new("Clear Cursors"),
+            )
+            .clicked()
+        {
+            plot.cursors = [None; 2];
+        }
+        if ui.button("Close").clicked() {
+            plot.open = false;
+        }
+    });
+
+    let Some(result) = result else {
+        ui.label("Run an analysis to plot its results");
+        return;
+    };
+
+    ui.label(&result.title);
+    ui.horizontal_wrapped(|ui| {
+        for (index, trace) in result.traces.iter().enumerate() {
+            let mut visible = plot.visible_traces.contains(&trace.name);
+            let text = egui::RichText::new(&trace.name).color(trace_color(index));
+            if ui.checkbox(&mut visible, text).changed() {
+                if visible {
+                    plot.visible_traces.insert(trace.name.clone());
+                } else {
+                    plot.visible_traces.remove(&trace.name);
+                }
+            }
+        }
+    });
+    ui.separator();
+
+    let visible_count = result
+        .traces
+        .iter()
+        .filter(|x| plot.visible_traces.contains(&x.name))
+        .count();
+    let readout_height = if plot.cursors.iter().any(|x| x.is_some()) {
+        (visible_count + 2) as f32 * READOUT_ROW_HEIGHT
+    } else {
+        0.0
+    };
+    let plot_height = (ui.available_height() - readout_height).max(120.0);
+
+    plot_area(ui, plot, result, plot_height);
+    cursor_readout(ui, plot, result);
+}
+
+fn plot_area(ui: &mut Ui, plot: &mut PlotState, result: &SimulationResult, height: f32) {
+    let size = Vec2::new(ui.available_width(), height);
+    let (response, painter) = ui.allocate_painter(size, Sense::click_and_drag());
+    let frame = response.rect;
+    let rect = Rect::from_min_max(
+        frame.min + Vec2::new(Y_AXIS_WIDTH, 0.0),
+        frame.max - Vec2::new(0.0, X_AXIS_HEIGHT),
+    );
+    if rect.width() <= 0.0 || rect.height() <= 0.0 {
+        return;
+    }
+
+    let mut bounds = plot
+        .bounds
+        .unwrap_or_else(|| auto_bounds(result, &plot.visible_traces));
+
+    if response.dragged_by(PointerButton::Middle) || response.dragged_by(PointerButton::Primary) {
+        let delta = response.drag_delta();
+        bounds.translate(
+            -(delta.x / rect.width()) as f64 * bounds.width(),
+            (delta.y / rect.height()) as f64 * bounds.height(),
+        );
+        plot.bounds = Some(bounds);
+    }
+
+    if let Some(pointer) = response.hover_pos() {
+        let (scroll, zoom) = ui.input(|x| (x.smooth_scroll_delta, x.zoom_delta()));
+        let factor = zoom as f64 * ((scroll.x + scroll.y) as f64 * 0.002).exp();
+        if factor != 1.0 {
+            // Over an axis only that axis is zoomed
+            let x_factor = if pointer.x < rect.left() { 1.0 } else { factor };
+            let y_factor = if pointer.y > rect.bottom() {
+                1.0
+            } else {
+                factor
+            };
+            bounds.zoom(bounds.plot_position(rect, pointer), x_factor, y_factor);
+            plot.bounds = Some(bounds);
+        }
+    }
+
+    if let Some(pointer) = response.interact_pointer_pos() {
+        let cursor = if response.clicked() {
+            Some(0)
+        } else if response.secondary_clicked() {
+            Some(1)
+        } else {
+            None
+        };
+        if let Some(cursor) = cursor {
+            plot.cursors[cursor] = Some(sweep_x(result, bounds.plot_position(rect, pointer).0));
+        }
+    }
+
+    let visuals = ui.visuals();
+    let text_color = visuals.text_color();
+    let grid_stroke = Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+    let font = FontId::monospace(11.0);
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+
+    let y_unit = axis_unit(result, &plot.visible_traces);
+    let x_tick_count = (rect.width() / MIN_TICK_SPACING).max(2.0) as f64;
+    for x in ticks(bounds.x_min, bounds.x_max, x_tick_count) {
+        let screen_x = bounds.screen_position(rect, x, 0.0).x;
+        painter.line_segment(
+            [
+                Pos2::new(screen_x, rect.top()),
+                Pos2::new(screen_x, rect.bottom()),
+            ],
+            grid_stroke,
+        );
+        painter.text(
+            Pos2::new(screen_x, rect.bottom() + 2.0),
+            Align2::CENTER_TOP,
+            format_si(sweep_x(result, x), result.sweep_unit),
+            font.clone(),
+            text_color,
+        );
+    }
+    let y_tick_count = (rect.height() / (MIN_TICK_SPACING * 0.5)).max(2.0) as f64;
+    for y in ticks(bounds.y_min, bounds.y_max, y_tick_count) {
+        let screen_y = bounds.screen_position(rect, 0.0, y).y;
+        painter.line_segment(
+            [
+                Pos2::new(rect.left(), screen_y),
+                Pos2::new(rect.right(), screen_y),
+            ],
+            grid_stroke,
+        );
+        painter.text(
+            Pos2::new(rect.left() - 4.0, screen_y),
+            Align2::RIGHT_CENTER,
+            format_si(y, y_unit),
+            font.clone(),
+            text_color,
+        );
+    }
+
+    let plot_painter = painter.with_clip_rect(rect);
+    for (index, trace) in visible_traces(result, &plot.visible_traces) {
+        let points = result
+            .sweep
+            .iter()
+            .zip(trace.values.iter())
+            .map(|(x, y)| bounds.screen_position(rect, axis_x(result, *x), *y))
+            .collect::<Vec<Pos2>>();
+        plot_painter.add(Shape::line(points, Stroke::new(1.5, trace_color(index))));
+    }
+
+    for (cursor, x) in plot.cursors.iter().enumerate() {
+        let Some(x) = x else {
+            continue;
+        };
+
+        let screen_x = bounds.screen_position(rect, axis_x(result, *x), 0.0).x;
+        let stroke = Stroke::new(1.0, CURSOR_COLORS[cursor]);
+        plot_painter.line_segment(
+            [
+                Pos2::new(screen_x, rect.top()),
+                Pos2::new(screen_x, rect.bottom()),
+            ],
+            stroke,
+        );
+        plot_painter.text(
+            Pos2::new(screen_x + 3.0, rect.top() + 2.0),
+            Align2::LEFT_TOP,
+            CURSOR_NAMES[cursor],
+            font.clone(),
+            CURSOR_COLORS[cursor],
+        );
+        for (index, trace) in visible_traces(result, &plot.visible_traces) {
+            if let Some(y) = interpolate(&result.sweep, &trace.values, *x) {
+                plot_painter.circle_filled(
+                    bounds.screen_position(rect, axis_x(result, *x), y),
+                    3.0,
+                    trace_color(index),
+                );
+            }
+        }
+    }
+
+    if plot.bounds.is_some() {
+        plot.bounds = Some(bounds);
+    }
+}
+
+fn cursor_readout(ui: &mut Ui, plot: &PlotState, result: &SimulationResult) {
+    if plot.cursors.iter().all(|x| x.is_none()) {
+        return;
+    }
+
+    let delta = |a: Option<f64>, b: Option<f64>, unit: &str| match (a, b) {
+        (Some(a), Some(b)) => format_si(b - a, unit),
+        _ => String::new(),
+    };
+    let value = |x: Option<f64>, unit: &str| x.map(|x| format_si(x, unit)).unwrap_or_default();
+
+    egui::Grid::new("plot_cursors")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.label("");
+            ui.label(CURSOR_NAMES[0]);
+            ui.label(CURSOR_NAMES[1]);
+            ui.label("B - A");
+            ui.end_row();
+
+            let [a, b] = plot.cursors;
+            ui.label(&result.sweep_name);
+            ui.label(value(a, result.sweep_unit));
+            ui.label(value(b, result.sweep_unit));
+            ui.label(delta(a, b, result.sweep_unit));
+            ui.end_row();
+
+            for (index, trace) in visible_traces(result, &plot.visible_traces) {
+                let a = a.and_then(|x| interpolate(&result.sweep, &trace.values, x));
+                let b = b.and_then(|x| interpolate(&result.sweep, &trace.values, x));
+                ui.label(egui::RichText::new(&trace.name).color(trace_color(index)));
+                ui.label(value(a, trace.unit));
+                ui.label(value(b, trace.unit));
+                ui.label(delta(a, b, trace.unit));
+                ui.end_row();
+            }
+        });
+}
+
+fn visible_traces<'a>(
+    result: &'a SimulationResult,
+    visible: &'a HashSet<String>,
+) -> impl Iterator<Item = (usize, &'a Trace)> {
+    result
+        .traces
+        .iter()
+        .enumerate()
+        .filter(|(_, trace)| visible.contains(&trace.name))
+}
+
+/// Position of a sweep value on the x axis, its exponent when the sweep is logarithmic
+fn axis_x(result: &SimulationResult, x: f64) -> f64 {
+    match result.log_sweep {
+        true => x.log10(),
+        false => x,
+    }
+}
+
+/// Sweep value at a position on the x axis
+fn sweep_x(result: &SimulationResult, x: f64) -> f64 {
+    match result.log_sweep {
+        true => 10f64.powf(x),
+        false => x,
+    }
+}
+
+fn trace_color(index: usize) -> Color32 {
+    TRACE_COLORS[index % TRACE_COLORS.len()]
+}
+
+/// Unit of the y axis, empty when voltages and currents are mixed
+fn axis_unit(result: &SimulationResult, visible: &HashSet<String>) -> &'static str {
+    let mut units = visible_traces(result, visible).map(|(_, x)| x.unit);
+    let Some(unit) = units.next() else {
+        return "";
+    };
+
+    if units.all(|x| x == unit) {
+        unit
+    } else {
+        ""
+    }
+}
+
+/// Bounds fitting the whole sweep and every visible trace with a small margin
+fn auto_bounds(result: &SimulationResult, visible: &HashSet<String>) -> PlotBounds {
+    let sweep = result.sweep.iter().map(|x| axis_x(result, *x));
+    let (x_min, x_max) = min_max(sweep).unwrap_or((0.0, 1.0));
+    let values = visible_traces(result, visible).flat_map(|(_, x)| x.values.iter().copied());
+    let (y_min, y_max) = min_max(values).unwrap_or((-1.0, 1.0));
+
+    let (x_min, x_max) = expand_range(x_min, x_max, 0.0);
+    let (y_min, y_max) = expand_range(y_min, y_max, 0.05);
+
+    PlotBounds {
+        x_min,
+        x_max,
+        y_min,
+        y_max,
+    }
+}
+
+fn min_max(values: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    values
+        .filter(|x| x.is_finite())
+        .fold(None, |range, x| match range {
+            None => Some((x, x)),
+            Some((min, max)) => Some((min.min(x), max.max(x))),
+        })
+}
+
+/// Adds `margin` of the range on both sides, giving empty ranges a size
+fn expand_range(min: f64, max: f64, margin: f64) -> (f64, f64) {
+    let range = max - min;
+    if range <= f64::EPSILON * max.abs().max(min.abs()).max(1e-30) {
+        let half = if min == 0.0 { 1.0 } else { min.abs() * 0.1 };
+        return (min - half, max + half);
+    }
+
+    (min - range * margin, max + range * margin)
+}
+
+/// Round tick positions inside `min..=max`, at most about `max_count` of them
+fn ticks(min: f64, max: f64, max_count: f64) -> Vec<f64> {
+    let range = max - min;
+    if !(range.is_finite() && range > 0.0) {
+        return vec![];
+    }
+
+    let rough_step = range / max_count;
+    let magnitude = 10f64.powf(rough_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|x| x * magnitude)
+        .find(|x| *x >= rough_step)
+        .unwrap_or(10.0 * magnitude);
+
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last)
+        .map(|x| {
+            let tick = x as f64 * step;
+            // Avoid labels such as 1e-17 for zero
+            if tick.abs() < step * 1e-9 {
+                0.0
+            } else {
+                tick
+            }
+        })
+        .collect()
+}
+
+/// Linear interpolation of `values` at `x`, `None` outside of the sweep
+pub fn interpolate(sweep: &[f64], values: &[f64], x: f64) -> Option<f64> {
+    if sweep.len() == 1 {
+        return (sweep[0] == x).then(|| values[0]);
+    }
+
+    sweep
+        .windows(2)
+        .zip(values.windows(2))
+        .find(|(xs, _)| xs[0].min(xs[1]) <= x && x <= xs[0].max(xs[1]))
+        .map(|(xs, ys)| {
+            let width = xs[1] - xs[0];
+            if width == 0.0 {
+                ys[0]
+            } else {
+                ys[0] + (ys[1] - ys[0]) * (x - xs[0]) / width
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashSet;
+
+    use crate::analysis::{SimulationResult, Trace};
+
+    use super::{auto_bounds, interpolate, ticks, PlotState};
+
+    fn result() -> SimulationResult {
+        SimulationResult {
+            title: "Transient".to_string(),
+            sweep_name: "Time".to_string(),
+            sweep_unit: "s",
+            log_sweep: false,
+            sweep: vec![0.0, 1.0, 2.0],
+            traces: vec![
+                Trace {
+                    name: "V1".to_string(),
+                    unit: "V",
+                    values: vec![0.0, 2.0, 4.0],
+                },
+                Trace {
+                    name: "I1".to_string(),
+                    unit: "A",
+                    values: vec![-1.0, -1.0, -1.0],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn interpolation() {
+        let result = result();
+        let values = &result.traces[0].values;
+
+        assert_eq!(interpolate(&result.sweep, values, 0.5), Some(1.0));
+        assert_eq!(interpolate(&result.sweep, values, 2.0), Some(4.0));
+        assert_eq!(interpolate(&result.sweep, values, 3.0), None);
+    }
+
+    #[test]
+    fn tick_steps() {
+        let expected = [0.0, 0.2, 0.4, 0.6, 0.8, 1.0];
+        let actual = ticks(0.0, 1.0, 5.0);
+        assert_eq!(actual.len(), expected.len());
+        assert!(actual
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| (a - b).abs() < 1e-12));
+        assert_eq!(ticks(-3.0, 12.0, 3.0), vec![0.0, 5.0, 10.0]);
+        assert!(ticks(1.0, 1.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn autoscale_visible_traces() {
+        let result = result();
+        let visible = HashSet::from_iter(["I1".to_string()]);
+        let bounds = auto_bounds(&result, &visible);
+
+        assert_eq!((bounds.x_min, bounds.x_max), (0.0, 2.0));
+        assert!(bounds.y_min < -1.0 && bounds.y_max > -1.0);
+
+        let result = SimulationResult {
+            log_sweep: true,
+            sweep: vec![10.0, 100.0, 1000.0],
+            ..result
+        };
+        let bounds = auto_bounds(&result, &visible);
+        assert!((bounds.x_min - 1.0).abs() < 1e-12 && (bounds.x_max - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn new_result_keeps_visible_traces() {
+        let mut plot = PlotState::default();
+        plot.show_result(&result());
+        assert_eq!(plot.visible_traces, HashSet::from_iter(["V1".to_string()]));
+
+        plot.visible_traces.insert("I1".to_string());
+        plot.visible_traces.insert("V9".to_string());
+        plot.show_result(&result());
+        assert_eq!(plot.visible_traces.len(), 2);
+        assert!(plot.open);
+    }
+}
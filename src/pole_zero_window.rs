@@ -0,0 +1,184 @@
+use egui::{Align2, Color32, Context, FontId, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+
+use crate::{
+    analysis::{
+        complex::Complex,
+        pole_zero::{natural_frequency, quality_factor, PoleZeroResult},
+    },
+    app::SpiceGuIrsApp,
+    utils::si::format_si,
+};
+
+const MARKER_SIZE: f32 = 5.0;
+const POLE_COLOR: Color32 = Color32::from_rgb(255, 99, 71);
+const ZERO_COLOR: Color32 = Color32::from_rgb(100, 149, 237);
+
+/// Result of the last pole-zero run shown on the s-plane
+pub struct PoleZeroView {
+    /// Input and output the transfer function is taken between
+    pub title: String,
+    pub result: PoleZeroResult,
+    pub open: bool,
+}
+
+impl PoleZeroView {
+    pub fn new(title: String, result: PoleZeroResult) -> Self {
+        Self {
+            title,
+            result,
+            open: true,
+        }
+    }
+}
+
+pub fn handle_pole_zero_window(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let Some(view) = app.pole_zero.as_mut() else {
+        return;
+    };
+    if !view.open {
+        return;
+    }
+
+    let mut open = true;
+    egui::Window::new("Pole-Zero")
+        .default_size(Vec2::new(360.0, 420.0))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(&view.title);
+            s_plane(ui, &view.result);
+            root_table(ui, &view.result);
+        });
+    view.open &= open;
+}
+
+/// Half width of the square s-plane region around the origin that holds every root
+fn plane_extent(result: &PoleZeroResult) -> f64 {
+    let largest = result
+        .poles
+        .iter()
+        .chain(result.zeros.iter())
+        .map(|x| x.re.abs().max(x.im.abs()))
+        .fold(0.0, f64::max);
+
+    if largest == 0.0 {
+        1.0
+    } else {
+        largest * 1.2
+    }
+}
+
+/// Poles drawn as crosses and zeros as circles on axes through the origin, both axes share their
+/// scale so angles and damping read correctly
+fn s_plane(ui: &mut Ui, result: &PoleZeroResult) {
+    let side = ui.available_width().min(320.0);
+    let (response, painter) = ui.allocate_painter(Vec2::splat(side), Sense::hover());
+    let rect = response.rect;
+    let extent = plane_extent(result);
+    let to_screen = |root: Complex| {
+        rect.center()
+            + Vec2::new(
+                (root.re / extent) as f32 * rect.width() / 2.0,
+                -(root.im / extent) as f32 * rect.height() / 2.0,
+            )
+    };
+
+    let visuals = ui.visuals();
+    let axis_stroke = Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+    let font = FontId::monospace(11.0);
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    painter.line_segment([rect.left_center(), rect.right_center()], axis_stroke);
+    painter.line_segment([rect.center_top(), rect.center_bottom()], axis_stroke);
+    painter.text(
+        rect.right_center() + Vec2::new(-2.0, 2.0),
+        Align2::RIGHT_TOP,
+        format_si(extent, "rad/s"),
+        font.clone(),
+        visuals.text_color(),
+    );
+    painter.text(
+        rect.center_top() + Vec2::new(4.0, 2.0),
+        Align2::LEFT_TOP,
+        format!("j{}", format_si(extent, "rad/s")),
+        font.clone(),
+        visuals.text_color(),
+    );
+
+    let pole_stroke = Stroke::new(2.0, POLE_COLOR);
+    for pole in result.poles.iter() {
+        let center = to_screen(*pole);
+        for corner in [Vec2::new(1.0, 1.0), Vec2::new(1.0, -1.0)] {
+            let offset = corner * MARKER_SIZE;
+            painter.line_segment([center - offset, center + offset], pole_stroke);
+        }
+    }
+    for zero in result.zeros.iter() {
+        painter.circle_stroke(to_screen(*zero), MARKER_SIZE, Stroke::new(2.0, ZERO_COLOR));
+    }
+
+    // Coordinates of the root under the pointer
+    let hovered = response.hover_pos().and_then(|pointer| {
+        result
+            .poles
+            .iter()
+            .chain(result.zeros.iter())
+            .map(|x| (*x, to_screen(*x).distance(pointer)))
+            .filter(|(_, distance)| *distance < MARKER_SIZE * 2.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    });
+    if let Some((root, _)) = hovered {
+        painter.text(
+            clamp_label(rect, to_screen(root)),
+            Align2::LEFT_BOTTOM,
+            format_root(root),
+            font,
+            visuals.strong_text_color(),
+        );
+    }
+}
+
+fn clamp_label(rect: Rect, position: Pos2) -> Pos2 {
+    Pos2::new(
+        position.x.min(rect.right() - 160.0).max(rect.left()) + MARKER_SIZE,
+        position.y.max(rect.top() + 14.0) - MARKER_SIZE,
+    )
+}
+
+/// Root in rectangular form such as `-5 krad/s + j8.66 krad/s`
+pub fn format_root(root: Complex) -> String {
+    format!(
+        "{} {} j{}",
+        format_si(root.re, "rad/s"),
+        if root.im < 0.0 { "-" } else { "+" },
+        format_si(root.im.abs(), "rad/s")
+    )
+}
+
+fn root_table(ui: &mut Ui, result: &PoleZeroResult) {
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("pole_zero_roots")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                for heading in ["", "s", "f0", "Q"] {
+                    ui.strong(heading);
+                }
+                ui.end_row();
+
+                let roots = result
+                    .poles
+                    .iter()
+                    .map(|x| ("Pole", POLE_COLOR, x))
+                    .chain(result.zeros.iter().map(|x| ("Zero", ZERO_COLOR, x)));
+                for (kind, color, root) in roots {
+                    ui.colored_label(color, kind);
+                    ui.monospace(format_root(*root));
+                    ui.monospace(format_si(natural_frequency(*root), "Hz"));
+                    match quality_factor(*root) {
+                        Some(quality) => ui.monospace(format!("{:.4}", quality)),
+                        None => ui.monospace("-"),
+                    };
+                    ui.end_row();
+                }
+            });
+    });
+}
@@ -0,0 +1,843 @@
+use egui::{Context, DragValue, Ui};
+use hashbrown::HashMap;
+use spice_rs::runners::dc_op::dc_op;
+
+use crate::{
+    analysis::{
+        ac::{ac, SweepType},
+        dc_sweep::{dc_sweep, SweepSource},
+        monte_carlo::{monte_carlo, MonteCarloAnalysis},
+        parametric::{parametric_sweep, ParameterSpacing, ParametricAnalysis},
+        pole_zero::{natural_frequency, pole_zero, quality_factor},
+        sensitivity::{sensitivity, SensitivityResult},
+        transfer_function::{transfer_function, TransferFunctionSettings},
+        transient::{operating_point, transient, InitialConditions},
+        unknown_names, AnalysisError, SimulationResult,
+    },
+    app::SpiceGuIrsApp,
+    circuit::{ElementType, Netlist},
+    erc::run_erc,
+    monte_carlo_window::MonteCarloView,
+    pole_zero_window::{format_root, PoleZeroView},
+    spice_import::parse_spice_value,
+    utils::si::format_si,
+};
+
+/// Runner whose settings window is open
+#[derive(Clone, Copy, PartialEq)]
+pub enum RunnerWindow {
+    Transient,
+    Ac,
+    DcSweep,
+    Parametric,
+    MonteCarlo,
+    Sensitivity,
+    TransferFunction,
+    PoleZero,
+}
+
+/// Result of the last sensitivity run, its largest contributors are highlighted until the next
+/// edit
+pub struct SensitivityReport {
+    /// History revision the sensitivities were computed at
+    pub revision: u64,
+    pub result: SensitivityResult,
+    pub highlighted: usize,
+}
+
+pub fn handle_runner_windows(app: &mut SpiceGuIrsApp, ctx: &Context) {
+    let Some(runner_window) = app.runner_window else {
+        return;
+    };
+
+    let mut open = true;
+    let mut run = false;
+    let title = match runner_window {
+        RunnerWindow::Transient => "Transient Analysis",
+        RunnerWindow::Ac => "AC Sweep",
+        RunnerWindow::DcSweep => "DC Sweep",
+        RunnerWindow::Parametric => "Parametric Sweep",
+        RunnerWindow::MonteCarlo => "Monte Carlo",
+        RunnerWindow::Sensitivity => "DC Sensitivity",
+        RunnerWindow::TransferFunction => "Transfer Function",
+        RunnerWindow::PoleZero => "Pole-Zero Analysis",
+    };
+
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            match runner_window {
+                RunnerWindow::Transient => transient_settings(app, ui),
+                RunnerWindow::Ac => ac_settings(app, ui),
+                RunnerWindow::DcSweep => dc_sweep_settings(app, ui),
+                RunnerWindow::Parametric => parametric_settings(app, ui),
+                RunnerWindow::MonteCarlo => monte_carlo_settings(app, ui),
+                RunnerWindow::Sensitivity => sensitivity_settings(app, ui),
+                RunnerWindow::TransferFunction => transfer_function_settings(app, ui),
+                RunnerWindow::PoleZero => pole_zero_settings(app, ui),
+            }
+
+            ui.separator();
+            run = ui.button("Run").clicked();
+        });
+
+    if run {
+        match runner_window {
+            RunnerWindow::Transient => run_transient(app),
+            RunnerWindow::Ac => run_ac(app),
+            RunnerWindow::DcSweep => run_dc_sweep(app),
+            RunnerWindow::Parametric => run_parametric(app),
+            RunnerWindow::MonteCarlo => run_monte_carlo(app),
+            RunnerWindow::Sensitivity => run_sensitivity(app),
+            RunnerWindow::TransferFunction => run_transfer_function(app),
+            RunnerWindow::PoleZero => run_pole_zero(app),
+        }
+    }
+
+    if !open {
+        app.runner_window = None;
+    }
+}
+
+fn transient_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let settings = &mut app.transient_settings;
+
+    egui::Grid::new("transient_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Stop time");
+            ui.add(si_drag_value(&mut settings.stop_time, "s"));
+            ui.end_row();
+
+            ui.label("Time step");
+            ui.add(si_drag_value(&mut settings.time_step, "s"));
+            ui.end_row();
+
+            ui.label("Initial conditions");
+            ui.vertical(|ui| {
+                ui.radio_value(
+                    &mut settings.initial_conditions,
+                    InitialConditions::OperatingPoint,
+                    "Operating point",
+                );
+                ui.radio_value(
+                    &mut settings.initial_conditions,
+                    InitialConditions::Zero,
+                    "Zero (UIC)",
+                );
+            });
+            ui.end_row();
+        });
+}
+
+/// Designators of the voltage and current sources, sorted
+fn source_names(app: &SpiceGuIrsApp) -> Vec<String> {
+    let mut sources = app
+        .gui_circuit
+        .gui_elements
+        .values()
+        .filter(|x| {
+            matches!(
+                x.element,
+                ElementType::DCVoltageSource(_) | ElementType::DCCurrentSource(_)
+            )
+        })
+        .map(|x| x.name.clone())
+        .collect::<Vec<String>>();
+    sources.sort();
+
+    sources
+}
+
+fn ac_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let sources = source_names(app);
+    let settings = &mut app.ac_settings;
+
+    egui::Grid::new("ac_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Sweep");
+            ui.horizontal(|ui| {
+                for sweep_type in [SweepType::Linear, SweepType::Decade, SweepType::Octave] {
+                    ui.radio_value(
+                        &mut settings.sweep_type,
+                        sweep_type,
+                        sweep_type.display_name(),
+                    );
+                }
+            });
+            ui.end_row();
+
+            ui.label(match settings.sweep_type {
+                SweepType::Linear => "Points",
+                SweepType::Decade => "Points per decade",
+                SweepType::Octave => "Points per octave",
+            });
+            ui.add(DragValue::new(&mut settings.points).range(1..=10_000));
+            ui.end_row();
+
+            ui.label("Start frequency");
+            ui.add(si_drag_value(&mut settings.start_frequency, "Hz"));
+            ui.end_row();
+
+            ui.label("Stop frequency");
+            ui.add(si_drag_value(&mut settings.stop_frequency, "Hz"));
+            ui.end_row();
+
+            ui.label("Source");
+            let selected = match settings.source.as_str() {
+                "" => "First voltage source",
+                name => name,
+            };
+            egui::ComboBox::from_id_salt("ac_source")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(
+                        &mut settings.source,
+                        String::new(),
+                        "First voltage source",
+                    );
+                    for source in sources {
+                        ui.selectable_value(&mut settings.source, source.clone(), source);
+                    }
+                });
+            ui.end_row();
+
+            ui.label("AC magnitude");
+            ui.add(DragValue::new(&mut settings.magnitude).speed(0.01));
+            ui.end_row();
+
+            ui.label("AC phase");
+            ui.add(
+                DragValue::new(&mut settings.phase)
+                    .range(-180.0..=180.0)
+                    .suffix("°"),
+            );
+            ui.end_row();
+        });
+}
+
+fn run_ac(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.ac_settings.clone();
+    let log_sweep = settings.sweep_type != SweepType::Linear;
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| ac(&netlist, &settings))
+        .map(|x| x.bode(log_sweep));
+
+    report_result(app, result);
+}
+
+fn dc_sweep_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let sources = source_names(app);
+    let settings = &mut app.dc_sweep_settings;
+    if settings.primary.source.is_empty() {
+        settings.primary.source = sources.first().cloned().unwrap_or_default();
+    }
+
+    sweep_source_settings(ui, "dc_sweep_primary", &mut settings.primary, &sources);
+    ui.separator();
+    ui.checkbox(&mut settings.nested, "Nested second source");
+    if settings.nested {
+        sweep_source_settings(ui, "dc_sweep_secondary", &mut settings.secondary, &sources);
+    }
+}
+
+fn sweep_source_settings(
+    ui: &mut Ui,
+    id: &str,
+    sweep_source: &mut SweepSource,
+    sources: &[String],
+) {
+    egui::Grid::new(id).num_columns(2).show(ui, |ui| {
+        ui.label("Source");
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(&sweep_source.source)
+            .show_ui(ui, |ui| {
+                for source in sources {
+                    ui.selectable_value(&mut sweep_source.source, source.clone(), source);
+                }
+            });
+        ui.end_row();
+
+        ui.label("Start");
+        ui.add(si_drag_value(&mut sweep_source.start, "").range(f64::MIN..=f64::MAX));
+        ui.end_row();
+
+        ui.label("Stop");
+        ui.add(si_drag_value(&mut sweep_source.stop, "").range(f64::MIN..=f64::MAX));
+        ui.end_row();
+
+        ui.label("Step");
+        ui.add(si_drag_value(&mut sweep_source.step, ""));
+        ui.end_row();
+    });
+}
+
+fn run_dc_sweep(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.dc_sweep_settings.clone();
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| dc_sweep(&netlist, &settings, spice_operating_point));
+
+    report_result(app, result);
+}
+
+/// Operating point of the netlist solved by spice-rs
+fn spice_operating_point(netlist: &Netlist) -> Result<Vec<f64>, AnalysisError> {
+    dc_op(&netlist.circuit())
+        .map(|values| values.iter().map(|x| *x as f64).collect())
+        .map_err(|error| AnalysisError::Solver(error.to_string()))
+}
+
+fn run_transient(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.transient_settings;
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| transient(&netlist, &settings));
+
+    report_result(app, result);
+}
+
+/// Designators of the elements with a value, sorted
+fn valued_element_names(app: &SpiceGuIrsApp) -> Vec<String> {
+    let mut elements = app
+        .gui_circuit
+        .gui_elements
+        .values()
+        .filter(|x| x.element.value().is_some())
+        .map(|x| x.name.clone())
+        .collect::<Vec<String>>();
+    elements.sort();
+
+    elements
+}
+
+fn parametric_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let elements = valued_element_names(app);
+    let settings = &mut app.parametric_settings;
+    if !elements.contains(&settings.element) {
+        settings.element = elements.first().cloned().unwrap_or_default();
+    }
+    let unit = app
+        .gui_circuit
+        .gui_elements
+        .values()
+        .find(|x| x.name == settings.element)
+        .and_then(|x| x.element.display_unit_symbol())
+        .unwrap_or_default();
+
+    egui::Grid::new("parametric_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Element");
+            egui::ComboBox::from_id_salt("parametric_element")
+                .selected_text(&settings.element)
+                .show_ui(ui, |ui| {
+                    for element in elements {
+                        ui.selectable_value(&mut settings.element, element.clone(), element);
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Values");
+            ui.horizontal(|ui| {
+                for spacing in [
+                    ParameterSpacing::List,
+                    ParameterSpacing::Linear,
+                    ParameterSpacing::Logarithmic,
+                ] {
+                    ui.radio_value(&mut settings.spacing, spacing, spacing.display_name());
+                }
+            });
+            ui.end_row();
+
+            if settings.spacing == ParameterSpacing::List {
+                ui.label("List");
+                ui.text_edit_singleline(&mut settings.list)
+                    .on_hover_text("Comma or space separated values such as 1k, 2.2k, 4.7k");
+                ui.end_row();
+            } else {
+                ui.label("Start");
+                ui.add(si_drag_value(&mut settings.start, unit));
+                ui.end_row();
+
+                ui.label("Stop");
+                ui.add(si_drag_value(&mut settings.stop, unit));
+                ui.end_row();
+
+                ui.label("Points");
+                ui.add(DragValue::new(&mut settings.points).range(1..=1000));
+                ui.end_row();
+            }
+
+            ui.label("Analysis");
+            egui::ComboBox::from_id_salt("parametric_analysis")
+                .selected_text(settings.analysis.display_name())
+                .show_ui(ui, |ui| {
+                    for analysis in [
+                        ParametricAnalysis::Transient,
+                        ParametricAnalysis::Ac,
+                        ParametricAnalysis::DcSweep,
+                    ] {
+                        ui.selectable_value(
+                            &mut settings.analysis,
+                            analysis,
+                            analysis.display_name(),
+                        );
+                    }
+                });
+            ui.end_row();
+        });
+    ui.label("The analysis runs with the settings of its own window");
+}
+
+fn run_parametric(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.parametric_settings.clone();
+    let transient_settings = app.transient_settings;
+    let ac_settings = app.ac_settings.clone();
+    let log_sweep = ac_settings.sweep_type != SweepType::Linear;
+    let dc_sweep_settings = app.dc_sweep_settings.clone();
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| {
+            parametric_sweep(&netlist, &settings, |netlist| match settings.analysis {
+                ParametricAnalysis::Transient => transient(netlist, &transient_settings),
+                ParametricAnalysis::Ac => ac(netlist, &ac_settings).map(|x| x.bode(log_sweep)),
+                ParametricAnalysis::DcSweep => {
+                    dc_sweep(netlist, &dc_sweep_settings, spice_operating_point)
+                }
+            })
+        });
+
+    report_result(app, result);
+}
+
+fn monte_carlo_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let settings = &mut app.monte_carlo_settings;
+
+    egui::Grid::new("monte_carlo_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Runs");
+            ui.add(DragValue::new(&mut settings.runs).range(1..=100_000));
+            ui.end_row();
+
+            ui.label("Seed");
+            ui.add(DragValue::new(&mut settings.seed));
+            ui.end_row();
+
+            ui.label("Analysis");
+            egui::ComboBox::from_id_salt("monte_carlo_analysis")
+                .selected_text(settings.analysis.display_name())
+                .show_ui(ui, |ui| {
+                    for analysis in [
+                        MonteCarloAnalysis::OperatingPoint,
+                        MonteCarloAnalysis::Transient,
+                        MonteCarloAnalysis::Ac,
+                    ] {
+                        ui.selectable_value(
+                            &mut settings.analysis,
+                            analysis,
+                            analysis.display_name(),
+                        );
+                    }
+                });
+            ui.end_row();
+        });
+
+    let toleranced = app
+        .gui_circuit
+        .gui_elements
+        .values()
+        .filter(|x| x.tolerance.percent > 0.0 && x.element.value().is_some())
+        .count();
+    ui.label(format!(
+        "{} elements with a tolerance, set in their properties",
+        toleranced
+    ));
+}
+
+fn run_monte_carlo(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.monte_carlo_settings.clone();
+    let transient_settings = app.transient_settings;
+    let ac_settings = app.ac_settings.clone();
+    let tolerances = app
+        .gui_circuit
+        .gui_elements
+        .values()
+        .map(|x| (x.id(), x.tolerance))
+        .collect::<HashMap<_, _>>();
+    let result =
+        app.gui_circuit
+            .netlist()
+            .ok_or(AnalysisError::NoGround)
+            .and_then(|netlist| {
+                monte_carlo(&netlist, &tolerances, &settings, |netlist| {
+                    match settings.analysis {
+                        MonteCarloAnalysis::OperatingPoint => spice_operating_point(netlist),
+                        MonteCarloAnalysis::Transient => transient(netlist, &transient_settings)
+                            .map(|result| {
+                                result
+                                    .traces
+                                    .iter()
+                                    .map(|x| x.values.last().copied().unwrap_or_default())
+                                    .collect()
+                            }),
+                        MonteCarloAnalysis::Ac => ac(netlist, &ac_settings).map(|result| {
+                            result
+                                .values
+                                .iter()
+                                .map(|x| x.last().map_or(0.0, |x| x.abs()))
+                                .collect()
+                        }),
+                    }
+                })
+            });
+
+    match result {
+        Ok(result) => {
+            app.terminal_lines.push("".to_string());
+            app.terminal_lines.push(result.title.clone());
+            for (index, (name, unit)) in result.names.iter().enumerate() {
+                let Some(statistics) = result.statistics(index).filter(|_| *unit == "V") else {
+                    continue;
+                };
+                app.terminal_lines.push(format!(
+                    "{}: min {}, max {}, mean {}, σ {}",
+                    name,
+                    format_si(statistics.min, unit),
+                    format_si(statistics.max, unit),
+                    format_si(statistics.mean, unit),
+                    format_si(statistics.std_dev, unit)
+                ));
+            }
+            app.monte_carlo = Some(MonteCarloView::new(result));
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+fn sensitivity_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let outputs = app
+        .gui_circuit
+        .netlist()
+        .map(|x| unknown_names(&x))
+        .unwrap_or_default();
+    let settings = &mut app.sensitivity_settings;
+    if !outputs.iter().any(|(name, _)| *name == settings.output) {
+        settings.output = outputs
+            .first()
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+    }
+
+    egui::Grid::new("sensitivity_settings")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Output");
+            egui::ComboBox::from_id_salt("sensitivity_output")
+                .selected_text(&settings.output)
+                .show_ui(ui, |ui| {
+                    for (name, _) in outputs {
+                        ui.selectable_value(&mut settings.output, name.clone(), name);
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Highlighted elements");
+            ui.add(DragValue::new(&mut settings.highlighted).range(0..=100));
+            ui.end_row();
+        });
+}
+
+fn run_sensitivity(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.sensitivity_settings.clone();
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| sensitivity(&netlist, &settings.output, operating_point));
+
+    match result {
+        Ok(result) => {
+            app.terminal_lines.push("".to_string());
+            app.terminal_lines.push(format!(
+                "Sensitivity of {} = {}",
+                result.output,
+                format_si(result.output_value, result.output_unit)
+            ));
+            for sensitivity in result.sensitivities.iter() {
+                let unit = format!("{}/{}", result.output_unit, sensitivity.unit);
+                let normalized = match sensitivity.normalized {
+                    Some(normalized) => format!("{:.4}", normalized),
+                    None => "-".to_string(),
+                };
+                app.terminal_lines.push(format!(
+                    "{}: {}, normalized {}",
+                    sensitivity.name,
+                    format_si(sensitivity.absolute, &unit),
+                    normalized
+                ));
+            }
+
+            app.sensitivity = Some(SensitivityReport {
+                revision: app.history.revision(),
+                result,
+                highlighted: settings.highlighted,
+            });
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+/// Node voltage names of the circuit, such as `V1` or `V(out)`
+fn node_names(app: &SpiceGuIrsApp) -> Vec<String> {
+    app.gui_circuit
+        .netlist()
+        .map(|netlist| {
+            unknown_names(&netlist)
+                .into_iter()
+                .take(netlist.node_count.saturating_sub(1))
+                .map(|(name, _)| name)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn transfer_function_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let sources = source_names(app);
+    let nodes = node_names(app);
+    let settings = &mut app.transfer_function_settings;
+    input_output_settings(ui, "transfer_function", settings, &sources, &nodes);
+}
+
+/// Input source and output node pair of a transfer function
+fn input_output_settings(
+    ui: &mut Ui,
+    id: &str,
+    settings: &mut TransferFunctionSettings,
+    sources: &[String],
+    nodes: &[String],
+) {
+    if !sources.contains(&settings.input) {
+        settings.input = sources.first().cloned().unwrap_or_default();
+    }
+    if !nodes.contains(&settings.output_positive) {
+        settings.output_positive = nodes.last().cloned().unwrap_or_default();
+    }
+    if !nodes.contains(&settings.output_negative) {
+        settings.output_negative = String::new();
+    }
+
+    egui::Grid::new(id).num_columns(2).show(ui, |ui| {
+        ui.label("Input source");
+        egui::ComboBox::from_id_salt((id, "input"))
+            .selected_text(&settings.input)
+            .show_ui(ui, |ui| {
+                for source in sources {
+                    ui.selectable_value(&mut settings.input, source.clone(), source);
+                }
+            });
+        ui.end_row();
+
+        ui.label("Output node");
+        egui::ComboBox::from_id_salt((id, "positive"))
+            .selected_text(&settings.output_positive)
+            .show_ui(ui, |ui| {
+                for node in nodes {
+                    ui.selectable_value(&mut settings.output_positive, node.clone(), node);
+                }
+            });
+        ui.end_row();
+
+        ui.label("Reference node");
+        let selected = match settings.output_negative.as_str() {
+            "" => "Ground",
+            name => name,
+        };
+        egui::ComboBox::from_id_salt((id, "negative"))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.output_negative, String::new(), "Ground");
+                for node in nodes {
+                    ui.selectable_value(&mut settings.output_negative, node.clone(), node);
+                }
+            });
+        ui.end_row();
+    });
+}
+
+/// Output node pair as written in reports, such as `V(out) - V2`
+fn output_name(settings: &TransferFunctionSettings) -> String {
+    match settings.output_negative.as_str() {
+        "" => settings.output_positive.clone(),
+        negative => format!("{} - {}", settings.output_positive, negative),
+    }
+}
+
+fn run_transfer_function(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.transfer_function_settings.clone();
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| transfer_function(&netlist, &settings, spice_operating_point));
+
+    match result {
+        Ok(result) => {
+            let output = output_name(&settings);
+            app.terminal_lines.push("".to_string());
+            app.terminal_lines.push(format!(
+                "Transfer function from {} to {}",
+                settings.input, output
+            ));
+            app.terminal_lines
+                .push(format!("Gain: {} {}", result.gain, result.gain_unit));
+            app.terminal_lines.push(format!(
+                "Input resistance at {}: {}",
+                settings.input,
+                format_si(result.input_resistance, "Ω")
+            ));
+            app.terminal_lines.push(format!(
+                "Output resistance at {}: {}",
+                output,
+                format_si(result.output_resistance, "Ω")
+            ));
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+fn pole_zero_settings(app: &mut SpiceGuIrsApp, ui: &mut Ui) {
+    let sources = source_names(app);
+    let nodes = node_names(app);
+    let settings = &mut app.pole_zero_settings;
+    input_output_settings(ui, "pole_zero", settings, &sources, &nodes);
+}
+
+fn run_pole_zero(app: &mut SpiceGuIrsApp) {
+    if !run_erc(app, false) {
+        return;
+    }
+
+    let settings = app.pole_zero_settings.clone();
+    let result = app
+        .gui_circuit
+        .netlist()
+        .ok_or(AnalysisError::NoGround)
+        .and_then(|netlist| pole_zero(&netlist, &settings));
+
+    match result {
+        Ok(result) => {
+            let title = format!("{} to {}", settings.input, output_name(&settings));
+            app.terminal_lines.push("".to_string());
+            app.terminal_lines
+                .push(format!("Poles and zeros from {}", title));
+            for (kind, roots) in [("Pole", &result.poles), ("Zero", &result.zeros)] {
+                for root in roots.iter() {
+                    let quality = match quality_factor(*root) {
+                        Some(quality) => format!(", Q {:.4}", quality),
+                        None => String::new(),
+                    };
+                    app.terminal_lines.push(format!(
+                        "{}: {}, f0 {}{}",
+                        kind,
+                        format_root(*root),
+                        format_si(natural_frequency(*root), "Hz"),
+                        quality
+                    ));
+                }
+            }
+            if result.poles.is_empty() && result.zeros.is_empty() {
+                app.terminal_lines
+                    .push("No poles or zeros, the circuit is resistive".to_string());
+            }
+
+            app.pole_zero = Some(PoleZeroView::new(title, result));
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+/// Keeps the result for the plot and prints a short summary
+fn report_result(app: &mut SpiceGuIrsApp, result: Result<SimulationResult, AnalysisError>) {
+    match result {
+        Ok(result) => {
+            app.terminal_lines.push("".to_string());
+            app.terminal_lines.push(format!(
+                "{}: {} points, {} traces",
+                result.title,
+                result.sweep.len(),
+                result.traces.len()
+            ));
+            for trace in result.traces.iter() {
+                if let Some(value) = trace.values.last() {
+                    app.terminal_lines.push(format!(
+                        "{} at {} = {}: {}",
+                        trace.name,
+                        result.sweep_name,
+                        format_si(*result.sweep.last().unwrap_or(&0.0), result.sweep_unit),
+                        format_si(*value, trace.unit)
+                    ));
+                }
+            }
+
+            app.plot.show_result(&result);
+            app.simulation_result = Some(result);
+        }
+        Err(error) => app.terminal_lines.push(format!("Error: {}", error)),
+    }
+}
+
+/// Drag value that shows and accepts engineering notation such as `10u`
+fn si_drag_value<'a>(value: &'a mut f64, unit: &'a str) -> DragValue<'a> {
+    let speed = value.abs() * 0.01;
+
+    DragValue::new(value)
+        .speed(speed)
+        .range(0.0..=f64::MAX)
+        .custom_formatter(move |x, _| format_si(x, unit))
+        .custom_parser(|text| {
+            // Mega is written `M` when formatted but `meg` in SPICE
+            parse_spice_value(&text.replace(' ', "").replace('M', "meg")).map(|x| x as f64)
+        })
+}
@@ -0,0 +1,372 @@
+use std::{fmt::Display, fs, path::Path};
+
+use egui::{Image, Pos2, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    circuit::{
+        oriented_image, ElementType, GuiCircuit, GuiElement, NetLabel, Tolerance, WireSegment,
+    },
+    utils::ipos2::{IPos2, Pos2Ext},
+};
+
+/// Version written into every saved schematic, bump it whenever the layout changes
+pub const SCHEMATIC_VERSION: u32 = 7;
+pub const SCHEMATIC_EXTENSION: &str = "sguirs";
+
+#[derive(Serialize, Deserialize)]
+pub struct SchematicFile {
+    pub version: u32,
+    pub elements: Vec<SavedElement>,
+    /// Straight wire segments, missing before version 5
+    #[serde(default)]
+    pub wires: Vec<WireSegment>,
+    /// Junction dots connecting crossing wires, missing before version 6
+    #[serde(default)]
+    pub junctions: Vec<IPos2>,
+    /// Wire polylines files before version 5 stored their wires as, only read
+    #[serde(default, skip_serializing)]
+    pub rendered_wires: Vec<Vec<Pos2>>,
+    /// Missing before version 3
+    #[serde(default)]
+    pub net_labels: Vec<NetLabel>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedElement {
+    pub element: ElementType,
+    /// Reference designator, missing in version 1 files and assigned on load
+    #[serde(default)]
+    pub name: String,
+    pub rect: Rect,
+    pub rotation: f32,
+    /// Mirroring along the element's own axes, missing before version 4
+    #[serde(default)]
+    pub flip: (bool, bool),
+    /// Missing before version 7, loaded as exact values
+    #[serde(default)]
+    pub tolerance: Tolerance,
+}
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+    InvalidWires,
+}
+
+impl Display for SchematicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchematicError::Io(error) => write!(f, "{}", error),
+            SchematicError::Parse(error) => write!(f, "Invalid schematic file: {}", error),
+            SchematicError::UnsupportedVersion(version) => write!(
+                f,
+                "Schematic version {} is newer than the supported version {}",
+                version, SCHEMATIC_VERSION
+            ),
+            SchematicError::InvalidWires => write!(f, "Schematic wires are not straight"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SchematicError {
+    fn from(error: std::io::Error) -> Self {
+        SchematicError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SchematicError {
+    fn from(error: serde_json::Error) -> Self {
+        SchematicError::Parse(error)
+    }
+}
+
+impl SchematicFile {
+    pub fn from_circuit(gui_circuit: &GuiCircuit) -> Self {
+        let mut gui_elements = gui_circuit.gui_elements.values().collect::<Vec<_>>();
+        gui_elements.sort_by_key(|x| x.id());
+        let elements = gui_elements
+            .into_iter()
+            .map(|x| SavedElement {
+                element: x.element,
+                name: x.name.clone(),
+                rect: x.rect,
+                rotation: x.rotation(),
+                flip: x.flip(),
+                tolerance: x.tolerance,
+            })
+            .collect();
+
+        Self {
+            version: SCHEMATIC_VERSION,
+            elements,
+            wires: gui_circuit.wire_segments().to_vec(),
+            junctions: gui_circuit.junctions().to_vec(),
+            rendered_wires: vec![],
+            net_labels: gui_circuit.net_labels.clone(),
+        }
+    }
+
+    pub fn into_circuit(self) -> Result<GuiCircuit, SchematicError> {
+        if self.version > SCHEMATIC_VERSION {
+            return Err(SchematicError::UnsupportedVersion(self.version));
+        }
+
+        let mut gui_circuit = GuiCircuit::default();
+        for saved_element in self.elements {
+            let image = oriented_image(
+                Image::new(saved_element.element.image_source()),
+                saved_element.rotation,
+                saved_element.flip,
+            );
+            let mut gui_element = GuiElement::new(saved_element.element, saved_element.rect, image);
+            gui_element.name = saved_element.name;
+            gui_element.tolerance = saved_element.tolerance;
+            gui_circuit.add_element(gui_element);
+        }
+
+        let mut wires = self.wires;
+        for polyline in self.rendered_wires.iter() {
+            for points in polyline.windows(2) {
+                let (start, end) = (points[0].to_ipos2(1), points[1].to_ipos2(1));
+                if start != end {
+                    wires.push(WireSegment::new(start, end));
+                }
+            }
+        }
+        if wires
+            .iter()
+            .any(|x| x.start.x != x.end.x && x.start.y != x.end.y)
+        {
+            return Err(SchematicError::InvalidWires);
+        }
+
+        // Crossing wires were connected before version 6, keep them connected with junctions
+        let mut junctions = self.junctions;
+        if self.version < 6 {
+            for (index, a) in wires.iter().enumerate() {
+                for b in wires[index + 1..].iter() {
+                    if let Some(position) = a.crossing(b).filter(|x| !junctions.contains(x)) {
+                        junctions.push(position);
+                    }
+                }
+            }
+        }
+        gui_circuit.set_wires(wires, junctions);
+        gui_circuit.net_labels = self.net_labels;
+
+        Ok(gui_circuit)
+    }
+
+    pub fn to_json(&self) -> Result<String, SchematicError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, SchematicError> {
+        Ok(serde_json::from_str(contents)?)
+    }
+}
+
+pub fn save_schematic(gui_circuit: &GuiCircuit, path: &Path) -> Result<(), SchematicError> {
+    let contents = SchematicFile::from_circuit(gui_circuit).to_json()?;
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+pub fn load_schematic(path: &Path) -> Result<GuiCircuit, SchematicError> {
+    let contents = fs::read_to_string(path)?;
+
+    SchematicFile::from_json(&contents)?.into_circuit()
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Pos2;
+    use hashbrown::HashSet;
+
+    use crate::{
+        circuit::{
+            add_test_element, Distribution, ElementType, GuiCircuit, NetLabel, Tolerance,
+            WireSegment,
+        },
+        utils::ipos2::IPos2,
+    };
+
+    use super::{SchematicError, SchematicFile, SCHEMATIC_VERSION};
+
+    #[test]
+    fn round_trip() {
+        let mut circuit = GuiCircuit::default();
+        add_test_element(
+            &mut circuit,
+            ElementType::DCVoltageSource(5.0),
+            Pos2::new(64.0, 64.0),
+            0.0,
+        );
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(220.0),
+            Pos2::new(160.0, 128.0),
+            std::f32::consts::FRAC_PI_2,
+        );
+        add_test_element(
+            &mut circuit,
+            ElementType::Ground,
+            Pos2::new(32.0, 160.0),
+            0.0,
+        );
+        circuit
+            .gui_elements
+            .values_mut()
+            .find(|x| matches!(x.element, ElementType::DCVoltageSource(_)))
+            .unwrap()
+            .flip_vertical();
+        circuit
+            .gui_elements
+            .values_mut()
+            .find(|x| matches!(x.element, ElementType::Resistor(_)))
+            .unwrap()
+            .tolerance = Tolerance {
+            percent: 5.0,
+            distribution: Distribution::Gaussian,
+        };
+        circuit.add_orthogonal_wires(IPos2::new(96, 64), IPos2::new(160, 96), true);
+        circuit.add_orthogonal_wires(IPos2::new(32, 64), IPos2::new(32, 144), true);
+        circuit.add_orthogonal_wires(IPos2::new(160, 160), IPos2::new(32, 144), false);
+        circuit.add_orthogonal_wires(IPos2::new(128, 32), IPos2::new(128, 96), true);
+        circuit.add_junction(IPos2::new(128, 64));
+        circuit.net_labels.push(NetLabel {
+            position: IPos2::new(128, 64),
+            name: "out".to_string(),
+        });
+
+        let contents = SchematicFile::from_circuit(&circuit).to_json().unwrap();
+        let loaded = SchematicFile::from_json(&contents)
+            .unwrap()
+            .into_circuit()
+            .unwrap();
+
+        assert_eq!(loaded.wire_segments(), circuit.wire_segments());
+        assert_eq!(loaded.junctions(), [IPos2::new(128, 64)]);
+        assert_eq!(loaded.net_labels, circuit.net_labels);
+        assert_eq!(loaded.node_groups, circuit.node_groups);
+
+        assert_eq!(loaded.gui_elements.len(), circuit.gui_elements.len());
+        for (id, element) in circuit.gui_elements.iter() {
+            let loaded_element = &loaded.gui_elements[id];
+            assert!(loaded_element.element == element.element);
+            assert_eq!(loaded_element.name, element.name);
+            assert_eq!(loaded_element.rect, element.rect);
+            assert_eq!(loaded_element.nodes, element.nodes);
+            assert!((loaded_element.rotation() - element.rotation()).abs() < 1e-6);
+            assert_eq!(loaded_element.flip(), element.flip());
+            assert!(loaded_element.tolerance == element.tolerance);
+        }
+
+        let pins = loaded
+            .gui_elements
+            .values()
+            .flat_map(|x| x.nodes.iter().copied())
+            .collect::<HashSet<IPos2>>();
+        assert!(pins
+            .iter()
+            .all(|pin| loaded.node_group_index(*pin).is_some()));
+    }
+
+    #[test]
+    fn version_4_wires_become_segments() {
+        let contents = r#"{
+            "version": 4,
+            "elements": [],
+            "nodes": [],
+            "nodes_starts": [],
+            "rendered_wires": [
+                [{"x": 0.0, "y": 0.0}, {"x": 32.0, "y": 0.0}, {"x": 32.0, "y": 32.0}],
+                [{"x": 64.0, "y": 64.0}, {"x": 64.0, "y": 64.0}, {"x": 96.0, "y": 64.0}]
+            ]
+        }"#;
+        let loaded = SchematicFile::from_json(contents)
+            .unwrap()
+            .into_circuit()
+            .unwrap();
+
+        assert_eq!(
+            loaded.wire_segments(),
+            [
+                WireSegment::new(IPos2::new(0, 0), IPos2::new(32, 0)),
+                WireSegment::new(IPos2::new(32, 0), IPos2::new(32, 32)),
+                WireSegment::new(IPos2::new(64, 64), IPos2::new(96, 64)),
+            ]
+        );
+        assert_eq!(loaded.node_groups, [vec![0, 1], vec![2]]);
+        assert!(loaded.junctions().is_empty());
+
+        let diagonal = contents.replace("{\"x\": 32.0, \"y\": 32.0}", "{\"x\": 40.0, \"y\": 32.0}");
+        assert!(matches!(
+            SchematicFile::from_json(&diagonal).unwrap().into_circuit(),
+            Err(SchematicError::InvalidWires)
+        ));
+    }
+
+    #[test]
+    fn version_5_crossings_get_junctions() {
+        let mut circuit = GuiCircuit::default();
+        circuit.add_orthogonal_wires(IPos2::new(0, 32), IPos2::new(64, 32), true);
+        circuit.add_orthogonal_wires(IPos2::new(32, 0), IPos2::new(32, 64), true);
+        assert_eq!(circuit.node_groups.len(), 2);
+
+        let mut file = SchematicFile::from_circuit(&circuit);
+        file.version = 5;
+        let loaded = file.into_circuit().unwrap();
+
+        assert_eq!(loaded.junctions(), [IPos2::new(32, 32)]);
+        assert_eq!(loaded.node_groups.len(), 1);
+    }
+
+    #[test]
+    fn version_1_gets_designators() {
+        let mut circuit = GuiCircuit::default();
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(220.0),
+            Pos2::new(64.0, 64.0),
+            0.0,
+        );
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(330.0),
+            Pos2::new(64.0, 160.0),
+            0.0,
+        );
+
+        let mut file = SchematicFile::from_circuit(&circuit);
+        file.version = 1;
+        let contents = file.to_json().unwrap().replace("\"name\"", "\"unused\"");
+        let loaded = SchematicFile::from_json(&contents)
+            .unwrap()
+            .into_circuit()
+            .unwrap();
+
+        let mut names = loaded
+            .gui_elements
+            .values()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<&str>>();
+        names.sort();
+        assert_eq!(names, ["R1", "R2"]);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut file = SchematicFile::from_circuit(&GuiCircuit::default());
+        file.version = SCHEMATIC_VERSION + 1;
+
+        assert!(matches!(
+            file.into_circuit(),
+            Err(SchematicError::UnsupportedVersion(_))
+        ));
+    }
+}
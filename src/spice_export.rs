@@ -0,0 +1,159 @@
+use std::{fmt::Write, fs, path::Path};
+
+use crate::circuit::{ElementType, GuiCircuit};
+
+pub const SPICE_EXTENSION: &str = "cir";
+
+pub struct ExportedNetlist {
+    pub deck: String,
+    /// Designators of the elements left out because a pin is not on a wire, in id order
+    pub omitted: Vec<String>,
+}
+
+/// Writes the circuit as a SPICE deck. Labeled nodes use their net label, the others the node
+/// numbers used by `construct_circuit` with ground as node 0. Returns `None` when the circuit
+/// has no connected ground.
+pub fn export_spice_netlist(gui_circuit: &GuiCircuit) -> Option<ExportedNetlist> {
+    let netlist = gui_circuit.netlist()?;
+
+    let mut omitted = gui_circuit
+        .gui_elements
+        .values()
+        .filter(|x| x.element != ElementType::Ground)
+        .filter(|x| netlist.elements.iter().all(|y| y.id != x.id()))
+        .map(|x| (x.id(), x.name.clone()))
+        .collect::<Vec<(u32, String)>>();
+    omitted.sort();
+
+    let mut deck = String::from("* Spice GuIrs netlist\n");
+    for netlist_element in netlist.elements.iter() {
+        let Some(value) = netlist_element.element.value() else {
+            continue;
+        };
+
+        let source_type = match netlist_element.element {
+            ElementType::DCVoltageSource(_) | ElementType::DCCurrentSource(_) => "DC ",
+            _ => "",
+        };
+
+        writeln!(
+            deck,
+            "{} {} {} {}{}",
+            netlist_element.name,
+            netlist.node_name(netlist_element.node1.0),
+            netlist.node_name(netlist_element.node2.0),
+            source_type,
+            value
+        )
+        .unwrap();
+    }
+    deck.push_str(".op\n.end\n");
+
+    Some(ExportedNetlist {
+        deck,
+        omitted: omitted.into_iter().map(|x| x.1).collect(),
+    })
+}
+
+/// Writes the deck to `path` and returns the designators that were left out of it
+pub fn save_spice_netlist(gui_circuit: &GuiCircuit, path: &Path) -> Result<Vec<String>, String> {
+    let exported = export_spice_netlist(gui_circuit).ok_or("Circuit has no connected ground")?;
+
+    fs::write(path, exported.deck).map_err(|error| error.to_string())?;
+    Ok(exported.omitted)
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Pos2;
+
+    use crate::{
+        circuit::{add_test_element, ElementType, GuiCircuit},
+        utils::ipos2::IPos2,
+    };
+
+    use super::export_spice_netlist;
+
+    #[test]
+    fn voltage_divider() {
+        let mut circuit = GuiCircuit::default();
+        // Pins at (0, 0) and (64, 0)
+        add_test_element(
+            &mut circuit,
+            ElementType::DCVoltageSource(5.0),
+            Pos2::new(32.0, 0.0),
+            0.0,
+        );
+        // Pins at (96, 0) and (160, 0)
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(1000.0),
+            Pos2::new(128.0, 0.0),
+            0.0,
+        );
+        // Pins at (192, 0) and (256, 0)
+        add_test_element(
+            &mut circuit,
+            ElementType::Resistor(2000.0),
+            Pos2::new(224.0, 0.0),
+            0.0,
+        );
+        // Pin at (0, 64)
+        add_test_element(&mut circuit, ElementType::Ground, Pos2::new(0.0, 80.0), 0.0);
+
+        circuit.add_orthogonal_wires(IPos2::new(64, 0), IPos2::new(96, 0), true);
+        circuit.add_orthogonal_wires(IPos2::new(160, 0), IPos2::new(192, 0), true);
+        circuit.add_orthogonal_wires(IPos2::new(0, 0), IPos2::new(0, 64), true);
+        circuit.add_orthogonal_wires(IPos2::new(256, 0), IPos2::new(0, 64), false);
+
+        let load = circuit
+            .gui_elements
+            .values_mut()
+            .find(|x| x.element == ElementType::Resistor(2000.0))
+            .unwrap();
+        load.name = "Rload".to_string();
+
+        // Not connected to anything, so it can not be part of the deck
+        add_test_element(
+            &mut circuit,
+            ElementType::Capacitor(1e-6),
+            Pos2::new(128.0, 160.0),
+            0.0,
+        );
+
+        let exported = export_spice_netlist(&circuit).unwrap();
+        assert_eq!(exported.omitted, ["C1"]);
+        let deck = exported.deck;
+        let lines = deck.lines().collect::<Vec<&str>>();
+
+        assert!(lines[0].starts_with('*'));
+        assert_eq!(lines[lines.len() - 2], ".op");
+        assert_eq!(lines[lines.len() - 1], ".end");
+
+        let cards = lines[1..lines.len() - 2]
+            .iter()
+            .map(|x| x.split_whitespace().collect::<Vec<&str>>())
+            .collect::<Vec<Vec<&str>>>();
+        assert_eq!(cards.len(), 3);
+
+        let source = &cards[0];
+        assert_eq!(source[0], "V1");
+        assert_eq!(source[1], "0");
+        assert_eq!(&source[3..], ["DC", "5"]);
+
+        let resistor1 = &cards[1];
+        assert_eq!(resistor1[0], "R1");
+        assert_eq!(resistor1[1], source[2]);
+        assert_eq!(resistor1[3], "1000");
+
+        let resistor2 = &cards[2];
+        assert_eq!(resistor2[0], "Rload");
+        assert_eq!(resistor2[1], resistor1[2]);
+        assert_eq!(resistor2[2], "0");
+        assert_eq!(resistor2[3], "2000");
+
+        assert_ne!(source[2], "0");
+        assert_ne!(resistor1[2], "0");
+        assert_ne!(source[2], resistor1[2]);
+    }
+}
@@ -0,0 +1,524 @@
+use std::{cmp::Reverse, collections::BinaryHeap, fs, path::Path};
+
+use egui::Image;
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    circuit::{is_ground_label, ElementType, GuiCircuit, GuiElement, NetLabel},
+    utils::ipos2::IPos2,
+    GRID_SIZE,
+};
+
+/// Space given to every imported element, leaving room for ground symbols and wire channels
+const CELL_WIDTH: i32 = GRID_SIZE * 12;
+const CELL_HEIGHT: i32 = GRID_SIZE * 10;
+/// Top left corner of the imported schematic, clear of the element bar
+const LAYOUT_ORIGIN: IPos2 = IPos2 {
+    x: GRID_SIZE * 8,
+    y: GRID_SIZE * 4,
+};
+/// Extra path length a wire is charged for every bend, keeps routes straight
+const TURN_COST: u32 = 4;
+const GROUND_NET: usize = usize::MAX;
+
+/// Two terminal element card read from a SPICE deck
+pub struct SpiceCard {
+    pub name: String,
+    pub element: ElementType,
+    pub nodes: [String; 2],
+}
+
+pub struct ImportedNetlist {
+    pub gui_circuit: GuiCircuit,
+    /// Unsupported cards and routing problems to show in the terminal
+    pub messages: Vec<String>,
+}
+
+pub fn load_spice_netlist(path: &Path) -> Result<ImportedNetlist, String> {
+    let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+
+    Ok(import_spice_netlist(&contents))
+}
+
+/// Parses R, C, L and DC V/I cards. Anything else is reported back instead of being dropped.
+pub fn parse_spice_netlist(contents: &str) -> (Vec<SpiceCard>, Vec<String>) {
+    let mut lines: Vec<String> = vec![];
+    // The first line of a SPICE deck is always its title
+    for line in contents.lines().skip(1) {
+        let line = line.split(';').next().unwrap_or_default().trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+
+        if let Some(continuation) = line.strip_prefix('+') {
+            if let Some(previous_line) = lines.last_mut() {
+                previous_line.push(' ');
+                previous_line.push_str(continuation.trim());
+            }
+            continue;
+        }
+
+        lines.push(line.to_string());
+    }
+
+    let mut cards = vec![];
+    let mut messages = vec![];
+    for line in lines {
+        let separated = line.replace(['(', ')', ','], " ");
+        let tokens = separated.split_whitespace().collect::<Vec<&str>>();
+        // Lines made only of separators have no card name
+        let Some(first_token) = tokens.first() else {
+            messages.push(format!("Unsupported card: {}", line));
+            continue;
+        };
+        let card_type = first_token.to_ascii_uppercase();
+
+        if card_type.starts_with('.') {
+            match card_type.as_str() {
+                ".END" => break,
+                ".OP" | ".TITLE" => (),
+                _ => messages.push(format!("Unsupported control line: {}", line)),
+            }
+            continue;
+        }
+
+        match card_type.chars().next() {
+            Some('R' | 'C' | 'L' | 'V' | 'I') => match parse_card(&tokens) {
+                Ok((card, ignored)) => {
+                    if !ignored.is_empty() {
+                        messages.push(format!("{}: ignored \"{}\"", card.name, ignored));
+                    }
+                    cards.push(card);
+                }
+                Err(error) => messages.push(error),
+            },
+            _ => messages.push(format!("Unsupported card: {}", line)),
+        }
+    }
+
+    (cards, messages)
+}
+
+/// Returns the card and any trailing tokens that could not be represented
+fn parse_card(tokens: &[&str]) -> Result<(SpiceCard, String), String> {
+    let name = tokens[0].to_string();
+    if tokens.len() < 4 {
+        return Err(format!("{}: missing nodes or value", name));
+    }
+
+    let prefix = name.chars().next().unwrap().to_ascii_uppercase();
+    let mut value_index = 3;
+    if matches!(prefix, 'V' | 'I') && tokens[3].eq_ignore_ascii_case("DC") {
+        value_index = 4;
+    }
+
+    let Some(value) = tokens.get(value_index).and_then(|x| parse_spice_value(x)) else {
+        return Err(match prefix {
+            'V' | 'I' => format!("{}: only DC sources are supported", name),
+            _ => format!("{}: invalid value", name),
+        });
+    };
+
+    let element = match prefix {
+        'R' => ElementType::Resistor(value),
+        'C' => ElementType::Capacitor(value),
+        'L' => ElementType::Inductor(value),
+        'V' => ElementType::DCVoltageSource(value),
+        _ => ElementType::DCCurrentSource(value),
+    };
+    let card = SpiceCard {
+        name,
+        element,
+        nodes: [tokens[1].to_string(), tokens[2].to_string()],
+    };
+
+    Ok((card, tokens[value_index + 1..].join(" ")))
+}
+
+/// Parses a SPICE number such as `4.7k`, `10uF` or `1meg`
+pub fn parse_spice_value(token: &str) -> Option<f32> {
+    let token = token.to_ascii_lowercase();
+    let bytes = token.as_bytes();
+
+    let mut number_end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        number_end += 1;
+    }
+    while number_end < bytes.len()
+        && (bytes[number_end].is_ascii_digit() || bytes[number_end] == b'.')
+    {
+        number_end += 1;
+    }
+    if bytes.get(number_end) == Some(&b'e') {
+        let mut exponent_end = number_end + 1;
+        if matches!(bytes.get(exponent_end), Some(b'+' | b'-')) {
+            exponent_end += 1;
+        }
+        if bytes.get(exponent_end).is_some_and(|x| x.is_ascii_digit()) {
+            while bytes.get(exponent_end).is_some_and(|x| x.is_ascii_digit()) {
+                exponent_end += 1;
+            }
+            number_end = exponent_end;
+        }
+    }
+
+    let number = token[..number_end].parse::<f64>().ok()?;
+    let suffix = &token[number_end..];
+    let multiplier = if suffix.starts_with("meg") {
+        1e6
+    } else if suffix.starts_with("mil") {
+        25.4e-6
+    } else {
+        match suffix.chars().next() {
+            Some('t') => 1e12,
+            Some('g') => 1e9,
+            Some('k') => 1e3,
+            Some('m') => 1e-3,
+            Some('u') => 1e-6,
+            Some('n') => 1e-9,
+            Some('p') => 1e-12,
+            Some('f') => 1e-15,
+            _ => 1.0,
+        }
+    };
+
+    Some((number * multiplier) as f32)
+}
+
+/// Places every card on a grid of cells, gives each grounded pin its own ground symbol and
+/// routes the remaining nets with wires that never touch another net. Net names are compared
+/// case insensitively like SPICE, named nets get a net label so they keep their name.
+pub fn import_spice_netlist(contents: &str) -> ImportedNetlist {
+    let (cards, mut messages) = parse_spice_netlist(contents);
+    let mut gui_circuit = GuiCircuit::default();
+
+    let columns = (cards.len() as f32).sqrt().ceil().max(1.0) as i32;
+    let rows = (cards.len() as i32 + columns - 1) / columns;
+    let mut router = Router {
+        min: IPos2::new(
+            LAYOUT_ORIGIN.x - GRID_SIZE * 2,
+            LAYOUT_ORIGIN.y - GRID_SIZE * 2,
+        ),
+        max: IPos2::new(
+            LAYOUT_ORIGIN.x + columns * CELL_WIDTH + GRID_SIZE * 2,
+            LAYOUT_ORIGIN.y + rows * CELL_HEIGHT + GRID_SIZE * 2,
+        ),
+        occupied: HashMap::new(),
+        blocked: HashSet::new(),
+    };
+
+    let mut nets: Vec<(&str, Vec<IPos2>)> = vec![];
+    let mut ground_stubs = vec![];
+    for (index, card) in cards.iter().enumerate() {
+        let index = index as i32;
+        let center = IPos2::new(
+            LAYOUT_ORIGIN.x + (index % columns) * CELL_WIDTH + CELL_WIDTH / 2,
+            LAYOUT_ORIGIN.y + (index / columns) * CELL_HEIGHT + CELL_HEIGHT / 2,
+        );
+        let mut gui_element = new_element(card.element, center);
+        // SPICE names are case insensitive, designators start with the upper case prefix
+        let mut name = card.name.clone();
+        name[..1].make_ascii_uppercase();
+        gui_element.name = name;
+        if let Err(error) = gui_circuit.validate_designator(u32::MAX, &gui_element) {
+            gui_element.name = gui_circuit.next_designator(&gui_element.element);
+            messages.push(format!(
+                "{}: {}, renamed to {}",
+                card.name, error, gui_element.name
+            ));
+        }
+        router.block_around(center, &[-1, 0, 1], &[-1, 0, 1]);
+
+        for (pin, net) in gui_element.nodes.clone().into_iter().zip(card.nodes.iter()) {
+            if is_ground_label(net) {
+                let ground_pin = pin + IPos2::new(0, GRID_SIZE * 2);
+                let ground_center = ground_pin + IPos2::new(0, GRID_SIZE);
+                router.occupy(pin, GROUND_NET);
+                router.occupy(pin + IPos2::new(0, GRID_SIZE), GROUND_NET);
+                router.occupy(ground_pin, GROUND_NET);
+                router.block_around(ground_center, &[-1, 0, 1], &[0, 1]);
+
+                gui_circuit.add_element(new_element(ElementType::Ground, ground_center));
+                ground_stubs.push((pin, ground_pin));
+                continue;
+            }
+
+            let net_index = match nets.iter().position(|x| x.0.eq_ignore_ascii_case(net)) {
+                Some(net_index) => net_index,
+                None => {
+                    nets.push((net, vec![]));
+                    nets.len() - 1
+                }
+            };
+            nets[net_index].1.push(pin);
+            router.occupy(pin, net_index);
+        }
+
+        gui_circuit.add_element(gui_element);
+    }
+
+    for (pin, ground_pin) in ground_stubs {
+        gui_circuit.add_orthogonal_wires(pin, ground_pin, false);
+    }
+
+    for (net_index, (net, pins)) in nets.iter().enumerate() {
+        let mut tree = HashSet::from([pins[0]]);
+        for pin in pins.iter().skip(1) {
+            if tree.contains(pin) {
+                continue;
+            }
+
+            let Some(path) = router.route(*pin, &tree, net_index) else {
+                messages.push(format!(
+                    "Could not route net {} without crossing another net",
+                    net
+                ));
+                continue;
+            };
+
+            for segment in path_segments(&path) {
+                gui_circuit.add_orthogonal_wires(segment.0, segment.1, segment.0.y == segment.1.y);
+            }
+            for position in path {
+                router.occupy(position, net_index);
+                tree.insert(position);
+            }
+        }
+
+        // Numbered nets are left to the node numbering, a label needs a wire to sit on
+        if NetLabel::validate_name(net).is_ok() && gui_circuit.node_group_index(pins[0]).is_some() {
+            gui_circuit.net_labels.push(NetLabel {
+                position: pins[0],
+                name: net.to_string(),
+            });
+        }
+    }
+
+    messages.push(format!("Imported {} elements", cards.len()));
+
+    ImportedNetlist {
+        gui_circuit,
+        messages,
+    }
+}
+
+fn new_element(element: ElementType, center: IPos2) -> GuiElement {
+    let image = Image::new(element.image_source());
+    let rect = GuiElement::rect_at(&image, center.to_pos2());
+
+    GuiElement::new(element, rect, image)
+}
+
+/// Collapses a grid path into its straight runs
+fn path_segments(path: &[IPos2]) -> Vec<(IPos2, IPos2)> {
+    let mut segments = vec![];
+    let mut segment_start = path[0];
+    for window in path.windows(3) {
+        let turns = (window[0].x == window[1].x) != (window[1].x == window[2].x);
+        if turns {
+            segments.push((segment_start, window[1]));
+            segment_start = window[1];
+        }
+    }
+    if let Some(end) = path.last() {
+        if *end != segment_start {
+            segments.push((segment_start, *end));
+        }
+    }
+
+    segments
+}
+
+/// Grid maze router. Points taken by a net can only be reused by that same net, and points
+/// under element images are avoided entirely.
+struct Router {
+    min: IPos2,
+    max: IPos2,
+    occupied: HashMap<IPos2, usize>,
+    blocked: HashSet<IPos2>,
+}
+
+impl Router {
+    fn occupy(&mut self, position: IPos2, net: usize) {
+        self.occupied.insert(position, net);
+    }
+
+    fn block_around(&mut self, center: IPos2, x_offsets: &[i32], y_offsets: &[i32]) {
+        for x in x_offsets {
+            for y in y_offsets {
+                self.blocked
+                    .insert(center + IPos2::new(x * GRID_SIZE, y * GRID_SIZE));
+            }
+        }
+    }
+
+    fn is_free(&self, position: IPos2, net: usize) -> bool {
+        position.x >= self.min.x
+            && position.y >= self.min.y
+            && position.x <= self.max.x
+            && position.y <= self.max.y
+            && !self.blocked.contains(&position)
+            && self.occupied.get(&position).is_none_or(|x| *x == net)
+    }
+
+    /// Cheapest path from `start` to any point of `targets`, both ends included
+    fn route(&self, start: IPos2, targets: &HashSet<IPos2>, net: usize) -> Option<Vec<IPos2>> {
+        // Direction index 4 means no direction yet
+        let mut costs: HashMap<(IPos2, usize), u32> = HashMap::new();
+        let mut previous: HashMap<(IPos2, usize), (IPos2, usize)> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        costs.insert((start, 4), 0);
+        queue.push(Reverse((0, start.x, start.y, 4)));
+
+        while let Some(Reverse((cost, x, y, direction))) = queue.pop() {
+            let position = IPos2::new(x, y);
+            if costs.get(&(position, direction)).is_some_and(|x| *x < cost) {
+                continue;
+            }
+
+            if targets.contains(&position) {
+                let mut path = vec![position];
+                let mut state = (position, direction);
+                while let Some(previous_state) = previous.get(&state) {
+                    path.push(previous_state.0);
+                    state = *previous_state;
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            for (next_direction, offset) in IPos2::DIRECTIONS.iter().enumerate() {
+                let next_position =
+                    position + IPos2::new(offset.x * GRID_SIZE, offset.y * GRID_SIZE);
+                if !self.is_free(next_position, net) {
+                    continue;
+                }
+
+                let mut next_cost = cost + 1;
+                if direction != 4 && direction != next_direction {
+                    next_cost += TURN_COST;
+                }
+
+                let next_state = (next_position, next_direction);
+                if costs.get(&next_state).is_some_and(|x| *x <= next_cost) {
+                    continue;
+                }
+                costs.insert(next_state, next_cost);
+                previous.insert(next_state, (position, direction));
+                queue.push(Reverse((
+                    next_cost,
+                    next_position.x,
+                    next_position.y,
+                    next_direction,
+                )));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+
+    use super::{import_spice_netlist, parse_spice_netlist, parse_spice_value};
+
+    #[test]
+    fn spice_values() {
+        assert_eq!(parse_spice_value("10"), Some(10.0));
+        assert_eq!(parse_spice_value("1k"), Some(1000.0));
+        assert_eq!(parse_spice_value("4.7K"), Some(4700.0));
+        assert_eq!(parse_spice_value("1Meg"), Some(1e6));
+        assert_eq!(parse_spice_value("2.2uF"), Some(2.2e-6));
+        assert_eq!(parse_spice_value("1e3"), Some(1000.0));
+        assert_eq!(parse_spice_value("5V"), Some(5.0));
+        assert_eq!(parse_spice_value("-3m"), Some(-3e-3));
+        assert_eq!(parse_spice_value("abc"), None);
+    }
+
+    #[test]
+    fn unsupported_cards_are_reported() {
+        let deck = "title\nR1 1 0 1k\nD1 1 0 diode\nV1 1 0 SIN(0 1 1k)\n.tran 1u 1m\n.end\n";
+        let (cards, messages) = parse_spice_netlist(deck);
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].contains("D1"));
+        assert!(messages[1].contains("V1"));
+        assert!(messages[2].contains(".tran"));
+    }
+
+    #[test]
+    fn separator_lines_are_reported() {
+        let deck = "title\n()\n,\nR1 1 0 1k\n";
+        let (cards, messages) = parse_spice_netlist(deck);
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(messages, ["Unsupported card: ()", "Unsupported card: ,"]);
+    }
+
+    #[test]
+    fn continuation_lines() {
+        let deck = "title\n* comment\nR1 1\n+ 0 2k ; inline comment\n";
+        let (cards, messages) = parse_spice_netlist(deck);
+
+        assert!(messages.is_empty());
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].nodes, ["1".to_string(), "0".to_string()]);
+        assert!(cards[0].element == crate::circuit::ElementType::Resistor(2000.0));
+    }
+
+    #[test]
+    fn imported_connectivity_matches_netlist() {
+        let deck = "filter\n\
+            V1 in 0 DC 5\n\
+            R1 in mid 1k\n\
+            R2 mid 0 2k\n\
+            C1 mid out 1u\n\
+            L1 OUT 0 1m\n\
+            R3 in out 10k\n\
+            I1 0 mid 1m\n\
+            .op\n\
+            .end\n";
+        let (cards, _) = parse_spice_netlist(deck);
+        let imported = import_spice_netlist(deck);
+        assert_eq!(imported.messages.len(), 1);
+
+        let netlist = imported.gui_circuit.netlist().unwrap();
+        assert_eq!(netlist.elements.len(), cards.len());
+        assert_eq!(netlist.node_count, 4);
+
+        let mut net_numbers: HashMap<String, usize> = HashMap::new();
+        for (card, netlist_element) in cards.iter().zip(netlist.elements.iter()) {
+            assert!(card.element == netlist_element.element);
+            assert_eq!(card.name, netlist_element.name);
+
+            for (net, node) in card
+                .nodes
+                .iter()
+                .zip([netlist_element.node1, netlist_element.node2])
+            {
+                if net == "0" {
+                    assert_eq!(node.0, 0);
+                    continue;
+                }
+
+                assert_ne!(node.0, 0);
+                let net = net.to_ascii_lowercase();
+                assert_eq!(*net_numbers.entry(net).or_insert(node.0), node.0);
+            }
+        }
+
+        let mut numbers = net_numbers.values().collect::<Vec<&usize>>();
+        numbers.sort();
+        numbers.dedup();
+        assert_eq!(numbers.len(), 3);
+
+        // Named nets keep their first spelling as net label
+        for net in ["in", "mid", "out"] {
+            assert_eq!(netlist.node_labels[net_numbers[net]].as_deref(), Some(net));
+        }
+    }
+}
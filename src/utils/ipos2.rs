@@ -1,8 +1,9 @@
 use std::ops::{Add, Rem};
 
 use egui::Pos2;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct IPos2 {
     pub x: i32,
     pub y: i32,
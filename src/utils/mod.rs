@@ -1,3 +1,5 @@
-pub mod double_range;
 pub mod ipos2;
+pub mod random;
+pub mod si;
+pub mod union_find;
 pub mod vec2ext;
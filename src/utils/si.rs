@@ -0,0 +1,42 @@
+const PREFIXES: [(f64, &str); 9] = [
+    (1e12, "T"),
+    (1e9, "G"),
+    (1e6, "M"),
+    (1e3, "k"),
+    (1.0, ""),
+    (1e-3, "m"),
+    (1e-6, "u"),
+    (1e-9, "n"),
+    (1e-12, "p"),
+];
+
+/// Formats a value with an engineering prefix, such as `4.7 kΩ` or `10 us`
+pub fn format_si(value: f64, unit: &str) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{} {}", value, unit);
+    }
+
+    let (scale, prefix) = PREFIXES
+        .iter()
+        .find(|(scale, _)| value.abs() >= *scale * (1.0 - 1e-9))
+        .unwrap_or(&PREFIXES[PREFIXES.len() - 1]);
+    let scaled = value / scale;
+    let formatted = format!("{:.4}", scaled);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    format!("{} {}{}", formatted, prefix, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_si;
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(format_si(4700.0, "Ω"), "4.7 kΩ");
+        assert_eq!(format_si(1e-5, "s"), "10 us");
+        assert_eq!(format_si(-0.25, "A"), "-250 mA");
+        assert_eq!(format_si(0.0, "V"), "0 V");
+        assert_eq!(format_si(3.0, "V"), "3 V");
+    }
+}
@@ -0,0 +1,61 @@
+/// Disjoint sets over `0..len` with path compression
+#[derive(Default, Clone)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    /// Adds a new set holding only the returned index
+    pub fn push(&mut self) -> usize {
+        self.parents.push(self.parents.len());
+        self.parents.len() - 1
+    }
+
+    pub fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        let mut index = index;
+        while self.parents[index] != root {
+            index = std::mem::replace(&mut self.parents[index], root);
+        }
+
+        root
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        // Keep the lower index as root so set order follows the first member
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnionFind;
+
+    #[test]
+    fn union_find() {
+        let mut sets = UnionFind::new(5);
+        sets.union(3, 4);
+        sets.union(4, 1);
+
+        assert_eq!(sets.find(3), 1);
+        assert_eq!(sets.find(4), 1);
+        assert_eq!(sets.find(0), 0);
+        assert_ne!(sets.find(2), sets.find(1));
+
+        assert_eq!(sets.push(), 5);
+        sets.union(5, 3);
+        assert_eq!(sets.find(5), 1);
+    }
+}